@@ -0,0 +1,279 @@
+//! ANSI escape sequences in task output.
+//!
+//! Lines are parsed on their own, every line starts with the default style.
+//! SGR sequences (`ESC [ ... m`) become styled spans, a carriage return or
+//! backspace moves the cursor back so progress output overwrites itself and
+//! `ESC [ K` erases the line. Other control and escape sequences are dropped.
+
+/// Colour of a span, as set by the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// One of the 256 palette colours, 0 to 15 are the terminal colours.
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// Red, green and blue of the colour, with the xterm palette.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        const BASIC: [(u8, u8, u8); 16] = [
+            (0, 0, 0),
+            (205, 49, 49),
+            (13, 188, 121),
+            (229, 229, 16),
+            (36, 114, 200),
+            (188, 63, 188),
+            (17, 168, 205),
+            (229, 229, 229),
+            (102, 102, 102),
+            (241, 76, 76),
+            (35, 209, 139),
+            (245, 245, 67),
+            (59, 142, 234),
+            (214, 112, 214),
+            (41, 184, 219),
+            (255, 255, 255),
+        ];
+        match self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Indexed(i @ 0..=15) => BASIC[i as usize],
+            Color::Indexed(i @ 16..=231) => {
+                let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+                let i = i - 16;
+                (level(i / 36), level(i / 6 % 6), level(i % 6))
+            }
+            Color::Indexed(i) => {
+                let grey = 8 + (i - 232) * 10;
+                (grey, grey, grey)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    /// Foreground colour, the default text colour when `None`.
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+    /// Foreground and background are swapped.
+    pub inverse: bool,
+}
+
+/// Text with one style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// Extended colour of `38`/`48` SGR parameters, `5;N` or `2;R;G;B`.
+fn extended(params: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut byte = || params.next().map(|n| n.min(255) as u8);
+    match byte()? {
+        5 => Some(Color::Indexed(byte()?)),
+        2 => Some(Color::Rgb(byte()?, byte()?, byte()?)),
+        _ => None,
+    }
+}
+
+impl Style {
+    /// Applies the parameters of an SGR sequence, an empty one resets.
+    fn apply(&mut self, params: &str) {
+        // `38:2::R:G:B` sub-parameters have an empty colour space id
+        let mut numbers = params
+            .split([';', ':'])
+            .filter(|p| !p.is_empty() || !params.contains(':'))
+            .map(|p| p.parse::<u16>().unwrap_or(0));
+        while let Some(n) = numbers.next() {
+            match n {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                2 => self.dim = true,
+                3 => self.italic = true,
+                4 => self.underline = true,
+                7 => self.inverse = true,
+                22 => (self.bold, self.dim) = (false, false),
+                23 => self.italic = false,
+                24 => self.underline = false,
+                27 => self.inverse = false,
+                30..=37 => self.fg = Some(Color::Indexed(n as u8 - 30)),
+                38 => self.fg = extended(&mut numbers),
+                39 => self.fg = None,
+                40..=47 => self.bg = Some(Color::Indexed(n as u8 - 40)),
+                48 => self.bg = extended(&mut numbers),
+                49 => self.bg = None,
+                90..=97 => self.fg = Some(Color::Indexed(n as u8 - 90 + 8)),
+                100..=107 => self.bg = Some(Color::Indexed(n as u8 - 100 + 8)),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Styled spans of `line` as a terminal would show it.
+pub fn parse(line: &str) -> Vec<Span> {
+    let mut cells: Vec<(char, Style)> = Vec::new();
+    let mut cursor = 0;
+    let mut style = Style::default();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameters, intermediates and a final byte
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut last = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            last = Some(c);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    match last {
+                        Some('m') => style.apply(&params),
+                        Some('K') => match params.as_str() {
+                            "" | "0" => cells.truncate(cursor),
+                            "1" => cells
+                                .iter_mut()
+                                .take(cursor + 1)
+                                .for_each(|cell| *cell = (' ', Style::default())),
+                            "2" => cells.clear(),
+                            _ => {}
+                        },
+                        _ => {}
+                    }
+                }
+                // OSC, e.g. titles and hyperlinks, ends with BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                // character set selection has one more byte
+                Some('(' | ')' | '*' | '+') => {
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\r' => cursor = 0,
+            '\x08' => cursor = cursor.saturating_sub(1),
+            '\t' => {
+                // tabs stop every 8 columns
+                let stop = (cursor / 8 + 1) * 8;
+                while cursor < stop {
+                    put(&mut cells, &mut cursor, ' ', style);
+                }
+            }
+            c if c.is_control() => {}
+            c => put(&mut cells, &mut cursor, c, style),
+        }
+    }
+
+    let mut spans: Vec<Span> = Vec::new();
+    for (c, style) in cells {
+        match spans.last_mut() {
+            Some(span) if span.style == style => span.text.push(c),
+            _ => spans.push(Span {
+                text: c.to_string(),
+                style,
+            }),
+        }
+    }
+    spans
+}
+
+/// Writes `c` at the cursor, over what was there.
+fn put(cells: &mut Vec<(char, Style)>, cursor: &mut usize, c: char, style: Style) {
+    if *cursor < cells.len() {
+        cells[*cursor] = (c, style);
+    } else {
+        cells.resize(*cursor, (' ', Style::default()));
+        cells.push((c, style));
+    }
+    *cursor += 1;
+}
+
+/// `line` without escape sequences, as it is shown.
+pub fn strip(line: &str) -> String {
+    parse(line).into_iter().map(|span| span.text).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, style: Style) -> Span {
+        Span {
+            text: text.to_string(),
+            style,
+        }
+    }
+
+    #[test]
+    fn test_sgr() {
+        let red_bold = Style {
+            fg: Some(Color::Indexed(1)),
+            bold: true,
+            ..Style::default()
+        };
+        assert_eq!(
+            parse("\x1b[1;31merror\x1b[0m: \x1b[4mmain.c\x1b[24m"),
+            vec![
+                span("error", red_bold),
+                span(": ", Style::default()),
+                span(
+                    "main.c",
+                    Style {
+                        underline: true,
+                        ..Style::default()
+                    }
+                ),
+            ]
+        );
+        let colours = |sgr: &str| parse(&format!("\x1b[{sgr}mx"))[0].style;
+        assert_eq!(colours("38;5;208").fg, Some(Color::Indexed(208)));
+        assert_eq!(colours("48;2;10;20;30").bg, Some(Color::Rgb(10, 20, 30)));
+        assert_eq!(colours("38:2::10:20:30").fg, Some(Color::Rgb(10, 20, 30)));
+        assert_eq!(colours("94").fg, Some(Color::Indexed(12)));
+        assert_eq!(colours("31;39"), Style::default());
+        assert_eq!(colours(""), Style::default());
+    }
+
+    #[test]
+    fn test_strips_other_sequences() {
+        assert_eq!(
+            strip("\x1b]0;title\x07a\x1b[2Ab\x1b(Bc\x1b]8;;url\x1b\\d\x07"),
+            "abcd"
+        );
+        assert_eq!(strip("a\tb"), "a       b");
+    }
+
+    #[test]
+    fn test_carriage_return() {
+        assert_eq!(
+            strip(" 10% [#   ]\r 50% [##  ]\r100% [####]"),
+            "100% [####]"
+        );
+        assert_eq!(strip("downloading...\rdone\x1b[K"), "done");
+        assert_eq!(strip("abc\x08\x08X"), "aXc");
+        let spans = parse("\x1b[32mok\x1b[0m\rX");
+        assert_eq!(spans[0], span("X", Style::default()));
+        assert_eq!(spans[1].style.fg, Some(Color::Indexed(2)));
+    }
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(Color::Indexed(1).rgb(), (205, 49, 49));
+        assert_eq!(Color::Indexed(16).rgb(), (0, 0, 0));
+        assert_eq!(Color::Indexed(196).rgb(), (255, 0, 0));
+        assert_eq!(Color::Indexed(232).rgb(), (8, 8, 8));
+        assert_eq!(Color::Rgb(1, 2, 3).rgb(), (1, 2, 3));
+    }
+}
@@ -0,0 +1,144 @@
+use getopts::Options;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::task_runners::Discovery;
+use crate::worker::{StopPolicy, StopSignal};
+
+/// Who is parsing the command line, shown by `--help` and `--version`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Program {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub version: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    CliExit,
+    InvalidOption(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    /// Build file to read, when not given every supported build file in the
+    /// working directory is used.
+    pub filename: Option<String>,
+    pub stop_policy: StopPolicy,
+    pub discovery: Discovery,
+    /// Directory tasks run in, instead of the one of their build file.
+    pub directory: Option<PathBuf>,
+    /// Run tasks in a pseudo-terminal unless their run options say not to.
+    pub pty: bool,
+}
+
+fn usage(program: &str, opts: &Options) -> String {
+    opts.usage(&format!("Usage: {} FILE [options]", program))
+}
+
+fn print_usage(about: &Program, program: &str, opts: Options) {
+    print!(
+        "Welcome to {}\n{}\n{}",
+        about.name,
+        about.description,
+        usage(program, &opts)
+    );
+}
+
+/// Parses the command line, exiting after `--help` or `--version`, and with
+/// status 2 and the usage after an invalid option.
+pub fn parse_args_or_exit(about: &Program, args: &[String]) -> Args {
+    match parse_args(about, args) {
+        Ok(args) => args,
+        Err(Error::CliExit) => std::process::exit(0),
+        Err(Error::InvalidOption(message)) => {
+            eprint!("{message}\n{}", usage(&args[0], &options()));
+            std::process::exit(2)
+        }
+    }
+}
+
+fn options() -> Options {
+    let mut opts = Options::new();
+    opts.optflag("h", "help", "print this help menu");
+    opts.optflag("v", "version", "prints version information");
+    opts.optopt(
+        "g",
+        "grace-period",
+        "seconds a stopped task has to exit before it is killed (default 5)",
+        "SECONDS",
+    );
+    opts.optopt(
+        "s",
+        "stop-signal",
+        "signal used to stop a task, INT or TERM (default INT)",
+        "SIGNAL",
+    );
+    opts.optflag(
+        "d",
+        "make-database",
+        "list make targets from `make -pRrq` instead of parsing makefiles",
+    );
+    opts.optopt(
+        "C",
+        "directory",
+        "run tasks in DIR instead of the build file's directory",
+        "DIR",
+    );
+    opts.optflag(
+        "t",
+        "pty",
+        "run tasks in a pseudo-terminal, so they print colours and progress",
+    );
+    opts
+}
+
+pub fn parse_args(about: &Program, args: &[String]) -> Result<Args, Error> {
+    let program = args[0].clone();
+    let opts = options();
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(f) => return Err(Error::InvalidOption(f.to_string())),
+    };
+    if matches.opt_present("h") {
+        print_usage(about, &program, opts);
+        return Err(Error::CliExit);
+    }
+    if matches.opt_present("v") {
+        println!("{}", about.version);
+        return Err(Error::CliExit);
+    }
+    let filename = matches.free.first().cloned();
+    let mut stop_policy = StopPolicy::default();
+    if let Some(seconds) = matches.opt_str("g") {
+        let seconds: f64 = seconds
+            .parse()
+            .map_err(|_| Error::InvalidOption(format!("invalid grace period: {seconds}")))?;
+        stop_policy.grace_period = Duration::try_from_secs_f64(seconds)
+            .map_err(|_| Error::InvalidOption(format!("invalid grace period: {seconds}")))?;
+    }
+    if let Some(signal) = matches.opt_str("s") {
+        stop_policy.signal = match signal.to_uppercase().trim_start_matches("SIG") {
+            "INT" => StopSignal::Interrupt,
+            "TERM" => StopSignal::Terminate,
+            _ => {
+                return Err(Error::InvalidOption(format!(
+                    "invalid stop signal: {signal}"
+                )))
+            }
+        };
+    }
+    let discovery = if matches.opt_present("d") {
+        Discovery::Database
+    } else {
+        Discovery::Parse
+    };
+    let directory = matches.opt_str("C").map(PathBuf::from);
+    Ok(Args {
+        filename,
+        stop_policy,
+        discovery,
+        directory,
+        pty: matches.opt_present("t"),
+    })
+}
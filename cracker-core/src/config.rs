@@ -0,0 +1,340 @@
+//! Settings from the user's `config.toml` and a project's `.cracker.toml`.
+//!
+//! ```toml
+//! theme = "Catppuccin Mocha"
+//! favourites = ["build", "test"]
+//! pty = true
+//!
+//! [output]
+//! max_lines = 1000000
+//! scrollbar_width = 15
+//! scrollbar_margin = 0
+//! scroller_width = 10
+//!
+//! [history]
+//! limit = 100
+//!
+//! # arguments given to every run of a program
+//! [args]
+//! make = ["-j8"]
+//!
+//! # key bindings added to the defaults, by command
+//! [keys]
+//! run = "enter"
+//! select-next = ["j", "ctrl-n"]
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::utils::{config_dir, Error};
+
+/// Name of the per-project config file.
+pub const PROJECT_FILE: &str = ".cracker.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Name of the ui theme, e.g. `Catppuccin Mocha`.
+    pub theme: Option<String>,
+    pub scrollbar_width: u16,
+    pub scrollbar_margin: u16,
+    pub scroller_width: u16,
+    /// Output lines kept for each run.
+    pub max_output_lines: usize,
+    /// Runs kept in the history.
+    pub history_limit: usize,
+    /// Arguments given to every run of a program, by program.
+    pub args: Vec<(String, Vec<String>)>,
+    /// Names of targets listed first.
+    pub favourites: Vec<String>,
+    /// Run tasks in a pseudo-terminal unless their run options say not to.
+    pub pty: bool,
+    /// Key bindings by command name.
+    pub keys: Vec<(String, Vec<String>)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            scrollbar_width: 15,
+            scrollbar_margin: 0,
+            scroller_width: 10,
+            max_output_lines: 1_000_000,
+            history_limit: 100,
+            args: Vec::new(),
+            favourites: Vec::new(),
+            pty: false,
+            keys: Vec::new(),
+        }
+    }
+}
+
+fn invalid(key: &str, expected: &str) -> Error {
+    Error::Parse(format!("`{key}` should be {expected}"))
+}
+
+fn integer<T: TryFrom<i64>>(table: &Table, key: &str, name: &str) -> Result<Option<T>, Error> {
+    table
+        .get(key)
+        .map(|item| {
+            item.as_integer()
+                .and_then(|n| T::try_from(n).ok())
+                .ok_or_else(|| invalid(name, "a positive integer"))
+        })
+        .transpose()
+}
+
+/// A string or an array of strings.
+fn strings(item: &Item, name: &str) -> Result<Vec<String>, Error> {
+    if let Some(s) = item.as_str() {
+        return Ok(vec![s.to_string()]);
+    }
+    item.as_array()
+        .and_then(|array| {
+            array
+                .iter()
+                .map(|value| value.as_str().map(String::from))
+                .collect()
+        })
+        .ok_or_else(|| invalid(name, "a string or an array of strings"))
+}
+
+fn table<'a>(root: &'a Table, key: &str) -> Result<Option<&'a Table>, Error> {
+    root.get(key)
+        .map(|item| item.as_table().ok_or_else(|| invalid(key, "a table")))
+        .transpose()
+}
+
+/// Sets `key` in `entries` to `value`, replacing an earlier setting.
+fn set(entries: &mut Vec<(String, Vec<String>)>, key: &str, value: Vec<String>) {
+    entries.retain(|(k, _)| k != key);
+    entries.push((key.to_string(), value));
+}
+
+impl Config {
+    /// Config files in the order they are applied, the user's and then the
+    /// project's in `project_dir`.
+    pub fn files(project_dir: &Path) -> Vec<PathBuf> {
+        config_dir()
+            .map(|dir| dir.join("config.toml"))
+            .into_iter()
+            .chain([project_dir.join(PROJECT_FILE)])
+            .collect()
+    }
+
+    /// Applies the settings in `text`, later files override earlier ones.
+    pub fn apply(&mut self, text: &str) -> Result<(), Error> {
+        let document: DocumentMut = text
+            .parse()
+            .map_err(|error: toml_edit::TomlError| Error::Parse(error.to_string()))?;
+        let root = document.as_table();
+        if let Some(theme) = root.get("theme") {
+            self.theme = Some(
+                theme
+                    .as_str()
+                    .ok_or_else(|| invalid("theme", "a string"))?
+                    .to_string(),
+            );
+        }
+        if let Some(favourites) = root.get("favourites") {
+            self.favourites = strings(favourites, "favourites")?;
+        }
+        if let Some(pty) = root.get("pty") {
+            self.pty = pty
+                .as_bool()
+                .ok_or_else(|| invalid("pty", "true or false"))?;
+        }
+        if let Some(output) = table(root, "output")? {
+            let name = |key| format!("output.{key}");
+            if let Some(n) = integer(output, "max_lines", &name("max_lines"))? {
+                self.max_output_lines = n;
+            }
+            if let Some(n) = integer(output, "scrollbar_width", &name("scrollbar_width"))? {
+                self.scrollbar_width = n;
+            }
+            if let Some(n) = integer(output, "scrollbar_margin", &name("scrollbar_margin"))? {
+                self.scrollbar_margin = n;
+            }
+            if let Some(n) = integer(output, "scroller_width", &name("scroller_width"))? {
+                self.scroller_width = n;
+            }
+        }
+        if let Some(history) = table(root, "history")? {
+            if let Some(n) = integer(history, "limit", "history.limit")? {
+                self.history_limit = n;
+            }
+        }
+        if let Some(args) = table(root, "args")? {
+            for (program, item) in args.iter() {
+                set(
+                    &mut self.args,
+                    program,
+                    strings(item, &format!("args.{program}"))?,
+                );
+            }
+        }
+        if let Some(keys) = table(root, "keys")? {
+            for (command, item) in keys.iter() {
+                set(
+                    &mut self.keys,
+                    command,
+                    strings(item, &format!("keys.{command}"))?,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Settings from `files`, skipping missing ones. Errors name the file
+    /// and leave the settings of the others in place.
+    pub fn load(files: &[PathBuf]) -> (Self, Vec<Error>) {
+        let mut config = Self::default();
+        let mut errors = Vec::new();
+        for file in files {
+            let text = match fs::read_to_string(file) {
+                Ok(text) => text,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(error) => {
+                    errors.push(Error::IoError(error.kind()));
+                    continue;
+                }
+            };
+            // a broken file changes nothing rather than half of its settings
+            let mut applied = config.clone();
+            match applied.apply(&text) {
+                Ok(()) => config = applied,
+                Err(Error::Parse(message)) => {
+                    errors.push(Error::Parse(format!("{}: {message}", file.display())))
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        (config, errors)
+    }
+
+    /// Arguments given to every run of `program`.
+    pub fn args(&self, program: &str) -> &[String] {
+        self.args
+            .iter()
+            .find(|(p, _)| p == program)
+            .map(|(_, args)| args.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Modification times of `files`, changing when any of them is edited,
+/// created or removed.
+pub fn modified(files: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut config = Config::default();
+        config
+            .apply(
+                r#"
+theme = "Dracula"
+favourites = ["build", "test"]
+pty = true
+
+[output]
+max_lines = 5000
+scroller_width = 8
+
+[history]
+limit = 20
+
+[args]
+make = ["-j8", "--no-print-directory"]
+
+[keys]
+run = "ctrl-r"
+select-next = ["j", "ctrl-n"]
+"#,
+            )
+            .unwrap();
+        assert_eq!(config.theme.as_deref(), Some("Dracula"));
+        assert_eq!(config.favourites, vec!["build", "test"]);
+        assert!(config.pty);
+        assert_eq!(config.max_output_lines, 5000);
+        assert_eq!(config.scroller_width, 8);
+        assert_eq!(config.scrollbar_width, 15);
+        assert_eq!(config.history_limit, 20);
+        assert_eq!(config.args("make"), ["-j8", "--no-print-directory"]);
+        assert!(config.args("npm").is_empty());
+        assert_eq!(
+            config.keys,
+            vec![
+                (String::from("run"), vec![String::from("ctrl-r")]),
+                (
+                    String::from("select-next"),
+                    vec![String::from("j"), String::from("ctrl-n")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_overrides_user() {
+        let mut config = Config::default();
+        config
+            .apply("theme = \"Dracula\"\n[args]\nmake = \"-j2\"\nnpm = \"--silent\"")
+            .unwrap();
+        config.apply("[args]\nmake = \"-j16\"").unwrap();
+        assert_eq!(config.theme.as_deref(), Some("Dracula"));
+        assert_eq!(config.args("make"), ["-j16"]);
+        assert_eq!(config.args("npm"), ["--silent"]);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut config = Config::default();
+        assert!(config.apply("theme = ").is_err());
+        assert_eq!(
+            config.apply("[output]\nmax_lines = -1"),
+            Err(Error::Parse(String::from(
+                "`output.max_lines` should be a positive integer"
+            )))
+        );
+        assert!(config.apply("favourites = [1]").is_err());
+        assert!(config.apply("history = 3").is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = std::env::temp_dir().join(format!("ck-config-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let user = dir.join("config.toml");
+        let project = dir.join(PROJECT_FILE);
+        fs::write(&user, "favourites = [\"test\"]\n[history]\nlimit = 10").unwrap();
+        fs::write(
+            &project,
+            "favourites = [\"lint\"]\n[history]\nlimit = \"ten\"",
+        )
+        .unwrap();
+        let files = [user, project.clone(), dir.join("missing.toml")];
+        let (config, errors) = Config::load(&files);
+        // the broken project file is left out whole
+        assert_eq!(config.favourites, vec!["test"]);
+        assert_eq!(config.history_limit, 10);
+        assert_eq!(errors.len(), 1);
+        assert!(modified(&files)[2].is_none());
+
+        fs::write(&project, "favourites = [\"lint\"]").unwrap();
+        let (config, errors) = Config::load(&files);
+        assert_eq!(config.favourites, vec!["lint"]);
+        assert!(errors.is_empty());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+//! Fuzzy matching of search text against target names.
+
+/// Score of a match and the positions, in chars, of the matched characters.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Match {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+const MATCH: i64 = 16;
+const CONSECUTIVE: i64 = 8;
+const WORD_START: i64 = 12;
+const GAP_START: i64 = 3;
+const GAP_EXTENSION: i64 = 1;
+
+/// A character starting a word, after a separator or a lower case letter.
+fn word_start(chars: &[char], i: usize) -> bool {
+    i == 0
+        || !chars[i - 1].is_alphanumeric()
+        || chars[i - 1].is_lowercase() && chars[i].is_uppercase()
+}
+
+/// Matches the characters of `pattern` in order, ignoring case, anywhere in
+/// `text`. Consecutive characters and word starts score higher, gaps lower.
+/// An empty pattern matches everything.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<Match> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+    let Some(first) = pattern.first() else {
+        return Some(Match::default());
+    };
+
+    // Each occurrence of the first character is tried as the start of a
+    // greedy match, the best scoring one wins.
+    let mut best: Option<Match> = None;
+    for start in (0..lower.len()).filter(|i| lower[*i] == *first) {
+        let mut positions = vec![start];
+        let mut i = start + 1;
+        for c in &pattern[1..] {
+            match (i..lower.len()).find(|j| lower[*j] == *c) {
+                Some(j) => {
+                    positions.push(j);
+                    i = j + 1;
+                }
+                None => break,
+            }
+        }
+        if positions.len() < pattern.len() {
+            // later starts can't find more characters
+            break;
+        }
+        let mut score = -(start as i64).min(10);
+        for (n, &position) in positions.iter().enumerate() {
+            score += MATCH;
+            if word_start(&chars, position) {
+                score += WORD_START;
+            }
+            if n > 0 {
+                let gap = position - positions[n - 1] - 1;
+                if gap == 0 {
+                    score += CONSECUTIVE;
+                } else {
+                    score -= GAP_START + GAP_EXTENSION * (gap as i64 - 1).min(10);
+                }
+            }
+        }
+        if best.as_ref().is_none_or(|b| score > b.score) {
+            best = Some(Match { score, positions });
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let m = fuzzy_match("bld", "build").unwrap();
+        assert_eq!(m.positions, vec![0, 3, 4]);
+        assert_eq!(
+            fuzzy_match("BR", "build-release").unwrap().positions,
+            vec![0, 6]
+        );
+        assert!(fuzzy_match("dlb", "build").is_none());
+        assert_eq!(fuzzy_match("", "build"), Some(Match::default()));
+    }
+
+    #[test]
+    fn test_ranking() {
+        let score = |pattern, text| fuzzy_match(pattern, text).unwrap().score;
+        // consecutive beats scattered
+        assert!(score("test", "test-all") > score("test", "the-best"));
+        // word starts beat the middle of words
+        assert!(score("di", "docker-image") > score("di", "addition"));
+        // the best start is used, not the first
+        assert_eq!(fuzzy_match("ci", "clean-ci").unwrap().positions, vec![6, 7]);
+    }
+}
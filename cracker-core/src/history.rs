@@ -0,0 +1,381 @@
+//! Finished runs kept on disk between sessions.
+//!
+//! Runs are listed in `index.jsonl`, one JSON object per line, oldest first.
+//! The output of each run is in `<id>.log`, every line prefixed with the
+//! file descriptor it was read from, `1 ` for stdout and `2 ` for stderr.
+
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::task_runners::RUNNERS;
+use crate::utils::{data_dir, Error};
+use crate::worker::{Exit, Line, RunOptions, Source, StdCommand};
+
+/// A finished run of a command.
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// Unique name of the run, its start time in nanoseconds.
+    pub id: String,
+    pub command: StdCommand,
+    pub exit: Exit,
+}
+
+fn seconds(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+fn time(seconds: f64) -> SystemTime {
+    UNIX_EPOCH + Duration::try_from_secs_f64(seconds).unwrap_or_default()
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s.as_str().map(String::from))
+        .collect()
+}
+
+fn options_json(options: &RunOptions) -> Value {
+    json!({
+        "variables": options.variables,
+        "jobs": options.jobs,
+        "keep_going": options.keep_going,
+        "directory": options.directory,
+        "extra_args": options.extra_args,
+        "pty": options.pty,
+    })
+}
+
+fn options_from_json(options: &Value) -> RunOptions {
+    RunOptions {
+        variables: strings(&options["variables"]),
+        jobs: options["jobs"].as_u64().map(|jobs| jobs as usize),
+        keep_going: options["keep_going"].as_bool().unwrap_or_default(),
+        directory: options["directory"].as_str().map(PathBuf::from),
+        extra_args: strings(&options["extra_args"]),
+        pty: options["pty"].as_bool().unwrap_or_default(),
+    }
+}
+
+impl Record {
+    fn to_json(&self) -> Value {
+        let command = &self.command;
+        let options = command.options();
+        json!({
+            "id": self.id,
+            "command_line": command.command_line(),
+            "program": command.program(),
+            "args": command.args(),
+            "target": command.target(),
+            "runner": command.runner().map(|runner| runner.name()),
+            "options": options_json(options),
+            "dir": command.dir(),
+            "code": self.exit.code,
+            "signal": self.exit.signal,
+            "stopped": self.exit.stopped,
+            "started": seconds(self.exit.started),
+            "ended": seconds(self.exit.ended),
+            "duration": self.exit.duration.as_secs_f64(),
+        })
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let options = options_from_json(&value["options"]);
+        let mut command = StdCommand::new(
+            value["target"].as_str()?.to_string(),
+            value["program"].as_str()?.to_string(),
+        )
+        .with_args(strings(&value["args"]))
+        .with_options(options);
+        if let Some(runner) = RUNNERS
+            .iter()
+            .find(|runner| Some(runner.name()) == value["runner"].as_str())
+        {
+            command = command.with_runner(*runner);
+        }
+        if let Some(dir) = value["dir"].as_str() {
+            command = command.in_dir(dir);
+        }
+        let exit = Exit {
+            code: value["code"].as_i64().map(|code| code as i32),
+            signal: value["signal"].as_i64().map(|signal| signal as i32),
+            stopped: value["stopped"].as_bool().unwrap_or_default(),
+            started: time(value["started"].as_f64()?),
+            ended: time(value["ended"].as_f64()?),
+            duration: Duration::try_from_secs_f64(value["duration"].as_f64()?).ok()?,
+        };
+        Some(Self {
+            id: value["id"].as_str()?.to_string(),
+            command,
+            exit,
+        })
+    }
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    Error::IoError(error.kind())
+}
+
+/// Runs saved in a directory.
+#[derive(Debug, Clone)]
+pub struct History {
+    dir: PathBuf,
+}
+
+impl History {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// History in the user's data directory.
+    pub fn open() -> Option<Self> {
+        data_dir().map(|dir| Self::new(dir.join("history")))
+    }
+
+    fn index(&self) -> PathBuf {
+        self.dir.join("index.jsonl")
+    }
+
+    fn log(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.log"))
+    }
+
+    fn run_options_file(&self) -> PathBuf {
+        self.dir.join("run_options.json")
+    }
+
+    /// Run options last used for targets, by their command line.
+    pub async fn load_run_options(&self) -> Result<HashMap<String, RunOptions>, Error> {
+        let saved = match fs::read_to_string(self.run_options_file()).await {
+            Ok(saved) => saved,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+            Err(error) => return Err(io_error(error)),
+        };
+        let saved: Value =
+            serde_json::from_str(&saved).map_err(|error| Error::Parse(error.to_string()))?;
+        Ok(saved
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(command_line, options)| (command_line.clone(), options_from_json(options)))
+            .collect())
+    }
+
+    /// Saves the run options last used for targets, by their command line.
+    pub async fn save_run_options(
+        &self,
+        options: &HashMap<String, RunOptions>,
+    ) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir).await.map_err(io_error)?;
+        let saved: Map<String, Value> = options
+            .iter()
+            .map(|(command_line, options)| (command_line.clone(), options_json(options)))
+            .collect();
+        fs::write(self.run_options_file(), Value::Object(saved).to_string())
+            .await
+            .map_err(io_error)
+    }
+
+    /// Saved runs, oldest first. Nothing has been saved without an index.
+    pub async fn load(&self) -> Result<Vec<Record>, Error> {
+        let index = match fs::read_to_string(self.index()).await {
+            Ok(index) => index,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(io_error(error)),
+        };
+        Ok(index
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| {
+                let record = serde_json::from_str(line)
+                    .ok()
+                    .and_then(|value| Record::from_json(&value));
+                if record.is_none() {
+                    warn!("skipping unreadable history entry: {line}");
+                }
+                record
+            })
+            .collect())
+    }
+
+    /// Saves a finished run with its output, keeping only the latest `keep`
+    /// runs.
+    pub async fn save(
+        &self,
+        command: StdCommand,
+        exit: Exit,
+        output: &[Line],
+        keep: usize,
+    ) -> Result<Record, Error> {
+        fs::create_dir_all(&self.dir).await.map_err(io_error)?;
+        let id = exit
+            .started
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            .to_string();
+        let record = Record { id, command, exit };
+
+        let mut log = String::new();
+        for line in output {
+            log.push_str(match line.source {
+                Source::Stdout => "1 ",
+                Source::Stderr => "2 ",
+            });
+            log.push_str(&line.text);
+            log.push('\n');
+        }
+        fs::write(self.log(&record.id), log)
+            .await
+            .map_err(io_error)?;
+
+        let mut index = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.index())
+            .await
+            .map_err(io_error)?;
+        index
+            .write_all(format!("{}\n", record.to_json()).as_bytes())
+            .await
+            .map_err(io_error)?;
+        // tokio finishes writes in the background, prune reads the index
+        index.flush().await.map_err(io_error)?;
+        self.prune(keep).await?;
+        debug!("saved run {} to history", record.id);
+        Ok(record)
+    }
+
+    /// Removes all but the latest `keep` runs.
+    async fn prune(&self, keep: usize) -> Result<(), Error> {
+        let records = self.load().await?;
+        if records.len() <= keep {
+            return Ok(());
+        }
+        let (old, kept) = records.split_at(records.len() - keep);
+        let index: String = kept
+            .iter()
+            .map(|record| format!("{}\n", record.to_json()))
+            .collect();
+        fs::write(self.index(), index).await.map_err(io_error)?;
+        for record in old {
+            if let Err(error) = fs::remove_file(self.log(&record.id)).await {
+                warn!("failed to remove log of run {}: {error}", record.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Output of a saved run.
+    pub async fn output(&self, record: &Record) -> Result<Vec<Line>, Error> {
+        let log = fs::read_to_string(self.log(&record.id))
+            .await
+            .map_err(io_error)?;
+        Ok(log
+            .lines()
+            .map(|line| match line.split_at_checked(2) {
+                Some(("2 ", text)) => Line::stderr(text),
+                Some(("1 ", text)) => Line::stdout(text),
+                _ => Line::stdout(line),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_runners::makefile;
+
+    fn history(name: &str) -> History {
+        let dir = std::env::temp_dir().join(format!("ck-history-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        History::new(dir)
+    }
+
+    fn exit(started: u64, code: i32) -> Exit {
+        let started = UNIX_EPOCH + Duration::from_secs(started);
+        Exit {
+            code: Some(code),
+            signal: None,
+            stopped: false,
+            started,
+            ended: started + Duration::from_millis(1500),
+            duration: Duration::from_millis(1500),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let history = history("save");
+        assert!(history.load().await.unwrap().is_empty());
+
+        let command = makefile::new("build".to_string())
+            .with_args(vec!["-f".to_string(), "Makefile".to_string()])
+            .with_options(RunOptions {
+                variables: vec!["V=1".to_string()],
+                jobs: Some(4),
+                ..RunOptions::default()
+            })
+            .in_dir("/src/app");
+        let output = [Line::stdout("cc main.c"), Line::stderr("warning: unused")];
+        let saved = history
+            .save(command, exit(1_700_000_000, 2), &output, 100)
+            .await
+            .unwrap();
+
+        let records = history.load().await.unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.id, saved.id);
+        assert_eq!(
+            record.command.command_line(),
+            "make -f Makefile -j4 V=1 build"
+        );
+        assert_eq!(record.command.dir(), Some(std::path::Path::new("/src/app")));
+        assert_eq!(record.exit, exit(1_700_000_000, 2));
+        assert_eq!(history.output(record).await.unwrap(), output);
+    }
+
+    #[tokio::test]
+    async fn test_keeps_latest_runs() {
+        let history = history("prune");
+        for started in 0..5 {
+            let command = StdCommand::new(format!("t{started}"), "make".to_string());
+            history
+                .save(command, exit(started, 0), &[], 3)
+                .await
+                .unwrap();
+        }
+        let records = history.load().await.unwrap();
+        let targets: Vec<&str> = records.iter().map(|r| r.command.target()).collect();
+        assert_eq!(targets, vec!["t2", "t3", "t4"]);
+        assert!(history.output(&records[0]).await.is_ok());
+        assert!(!history.log("0").exists());
+    }
+
+    #[tokio::test]
+    async fn test_run_options() {
+        let history = history("options");
+        assert!(history.load_run_options().await.unwrap().is_empty());
+        let options = HashMap::from([(
+            "make build".to_string(),
+            RunOptions {
+                variables: vec!["V=1".to_string()],
+                jobs: Some(4),
+                extra_args: vec!["a b".to_string()],
+                ..RunOptions::default()
+            },
+        )]);
+        history.save_run_options(&options).await.unwrap();
+        assert_eq!(history.load_run_options().await.unwrap(), options);
+    }
+}
@@ -0,0 +1,36 @@
+//! Target discovery and task execution for cracker, without any ui.
+//!
+//! ```no_run
+//! use cracker_core::{task_runners, worker};
+//! use futures::StreamExt;
+//! use std::path::PathBuf;
+//!
+//! # async fn example() {
+//! let groups = task_runners::discover(PathBuf::from("."), Default::default()).await;
+//! let command = groups[0].targets[0].command.clone();
+//!
+//! let mut output = Box::pin(worker::run(command));
+//! while let Some(Ok(update)) = output.next().await {
+//!     match update {
+//!         worker::Stdout::OutputUpdate { output } => {
+//!             output.iter().for_each(|line| println!("{}", line.text))
+//!         }
+//!         worker::Stdout::Finished(exit) => println!("exit code {:?}", exit.code),
+//!         _ => {}
+//!     }
+//! }
+//! # }
+//! ```
+#[macro_use]
+extern crate log;
+
+pub mod ansi;
+pub mod args;
+pub mod config;
+pub mod fuzzy;
+pub mod history;
+mod pty;
+pub mod search;
+pub mod task_runners;
+pub mod utils;
+pub mod worker;
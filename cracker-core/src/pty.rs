@@ -0,0 +1,45 @@
+//! Pseudo-terminals for tasks that should see a terminal rather than pipes,
+//! so they keep their colours, progress bars and line buffering.
+
+use nix::pty::{openpty, Winsize};
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd};
+
+/// Columns and rows of a terminal before the ui says how big it is.
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+fn winsize((cols, rows): (u16, u16)) -> Winsize {
+    Winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+/// A new terminal of `size` columns and rows, the end read by the worker
+/// and the end given to the task.
+pub(crate) fn open(size: (u16, u16)) -> io::Result<(OwnedFd, OwnedFd)> {
+    let pty = openpty(&winsize(size), None)?;
+    Ok((pty.master, pty.slave))
+}
+
+/// Tells the task its terminal is now `size` columns and rows.
+pub(crate) fn resize(master: &OwnedFd, size: (u16, u16)) -> io::Result<()> {
+    // SAFETY: TIOCSWINSZ only reads the winsize, which outlives the call.
+    match unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &winsize(size)) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Starts a new session with the terminal on stdin as its controlling
+/// terminal. Runs in the task between fork and exec.
+pub(crate) fn set_controlling_terminal() -> io::Result<()> {
+    nix::unistd::setsid()?;
+    // SAFETY: TIOCSCTTY takes no pointer.
+    match unsafe { libc::ioctl(0, libc::TIOCSCTTY, 0) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,88 @@
+//! Searching task output for plain text or a regular expression.
+
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+
+use crate::utils::Error;
+
+/// What to look for in task output.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub text: String,
+    /// `text` is a regular expression rather than plain text.
+    pub regex: bool,
+    pub case_sensitive: bool,
+}
+
+/// A compiled [`Query`].
+#[derive(Debug, Clone)]
+pub struct Searcher {
+    regex: Regex,
+}
+
+impl Searcher {
+    /// Searcher for `query`, `None` when there is nothing to look for.
+    pub fn new(query: &Query) -> Result<Option<Self>, Error> {
+        if query.text.is_empty() {
+            return Ok(None);
+        }
+        let pattern = match query.regex {
+            true => query.text.clone(),
+            false => regex::escape(&query.text),
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!query.case_sensitive)
+            .build()
+            .map_err(|error| Error::Parse(error.to_string()))?;
+        Ok(Some(Self { regex }))
+    }
+
+    /// Byte ranges of the matches in `text`, leaving out empty ones.
+    pub fn find<'a>(&'a self, text: &'a str) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.regex
+            .find_iter(text)
+            .map(|found| found.range())
+            .filter(|range| !range.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str, regex: bool, case_sensitive: bool) -> Query {
+        Query {
+            text: text.to_string(),
+            regex,
+            case_sensitive,
+        }
+    }
+
+    fn find(query: &Query, text: &str) -> Vec<Range<usize>> {
+        Searcher::new(query).unwrap().unwrap().find(text).collect()
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(
+            find(&query("a.c", false, false), "A.C abc a.c"),
+            vec![0..3, 8..11]
+        );
+        assert_eq!(find(&query("a.c", false, true), "A.C abc a.c"), vec![8..11]);
+        assert!(Searcher::new(&Query::default()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_regex() {
+        let warnings = query(r"warning: \w+", true, true);
+        assert_eq!(
+            find(&warnings, "main.c:3: warning: unused, Warning: x"),
+            vec![10..25]
+        );
+        assert_eq!(find(&query("x*", true, false), "axxb"), vec![1..3]);
+        assert!(matches!(
+            Searcher::new(&query("(", true, false)),
+            Err(Error::Parse(_))
+        ));
+    }
+}
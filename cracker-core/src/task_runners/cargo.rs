@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use crate::task_runners::{Target, TaskRunner};
+use crate::utils::Error;
+use crate::worker::StdCommand;
+
+/// Cargo has no user defined targets, so the everyday subcommands are listed.
+pub const SUBCOMMANDS: [&str; 9] = [
+    "build", "check", "test", "run", "bench", "doc", "clippy", "fmt", "clean",
+];
+
+pub fn new(subcommand: String) -> StdCommand {
+    StdCommand::new(subcommand, "cargo".to_string()).with_runner(&Cargo)
+}
+
+#[derive(Debug)]
+pub struct Cargo;
+
+impl TaskRunner for Cargo {
+    fn name(&self) -> &'static str {
+        "cargo"
+    }
+
+    fn files(&self) -> &'static [&'static str] {
+        &["Cargo.toml"]
+    }
+
+    /// A virtual workspace manifest has nothing to `cargo run`.
+    fn parse(&self, file: &Path, contents: &str) -> Result<Vec<Target>, Error> {
+        let package = contents.lines().any(|line| line.trim() == "[package]");
+        Ok(SUBCOMMANDS
+            .iter()
+            .filter(|subcommand| package || **subcommand != "run")
+            .map(|subcommand| Target::new(new(subcommand.to_string()), file))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Vec<String> {
+        Cargo
+            .parse(Path::new("Cargo.toml"), contents)
+            .unwrap()
+            .iter()
+            .map(|target| target.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_package_subcommands() {
+        let contents = "[package]\nname = \"ck\"\n";
+        let actual = parse(contents);
+        assert!(actual.contains(&String::from("run")));
+        assert_eq!(actual.len(), SUBCOMMANDS.len());
+    }
+
+    #[test]
+    fn test_workspace_has_no_run() {
+        let contents = "[workspace]\nmembers = [\"ck\"]\n";
+        let actual = parse(contents);
+        assert!(!actual.contains(&String::from("run")));
+        assert_eq!(actual.len(), SUBCOMMANDS.len() - 1);
+    }
+}
@@ -0,0 +1,120 @@
+use peg::*;
+use std::path::Path;
+
+use crate::task_runners::{Target, TaskRunner};
+use crate::utils::Error;
+use crate::worker::StdCommand;
+
+parser!(pub grammar parser() for str {
+    #[no_eof]
+    pub rule Recipe() -> String = "@"? n:Name() [^':']* ":" !"=" { n }
+        rule Name() -> String = n:$(['a'..='z'|'A'..='Z'|'_'] ['a'..='z'|'A'..='Z'|'0'..='9'|'_'|'-']*) {
+            n.to_string()
+        }
+});
+
+pub fn new(recipe: String) -> StdCommand {
+    StdCommand::new(recipe, "just".to_string()).with_runner(&Just)
+}
+
+#[derive(Debug)]
+pub struct Just;
+
+impl TaskRunner for Just {
+    fn name(&self) -> &'static str {
+        "just"
+    }
+
+    fn files(&self) -> &'static [&'static str] {
+        &["justfile", "Justfile", ".justfile"]
+    }
+
+    /// Public recipes; ones starting with `_` or marked `[private]` are
+    /// left out, as `just --list` does. The comment line above a recipe
+    /// describes it.
+    fn parse(&self, file: &Path, contents: &str) -> Result<Vec<Target>, Error> {
+        let mut recipes = Vec::new();
+        let mut private = false;
+        let mut comment = None;
+        for (number, line) in contents.lines().enumerate() {
+            if line.starts_with('[') {
+                private |= line.contains("private");
+                continue;
+            }
+            if let Some(text) = line.strip_prefix('#').filter(|text| !text.starts_with('!')) {
+                comment = Some(text.trim().to_string()).filter(|text| !text.is_empty());
+                continue;
+            }
+            if let Ok(recipe) = parser::Recipe(line) {
+                if !private && !recipe.starts_with('_') {
+                    let target = Target::new(new(recipe), file)
+                        .at_line(number + 1)
+                        .with_description(comment.clone());
+                    recipes.push(target);
+                }
+            }
+            private = false;
+            comment = None;
+        }
+        Ok(recipes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipe() {
+        let actual = parser::Recipe("build:").ok();
+        assert_eq!(actual, Some(String::from("build")));
+    }
+
+    #[test]
+    fn test_recipe_with_parameters_and_dependencies() {
+        let actual = parser::Recipe("deploy-app env=\"staging\" +args: build test").ok();
+        assert_eq!(actual, Some(String::from("deploy-app")));
+    }
+
+    #[test]
+    fn test_quiet_recipe() {
+        let actual = parser::Recipe("@serve:").ok();
+        assert_eq!(actual, Some(String::from("serve")));
+    }
+
+    #[test]
+    fn test_ignore_assignment() {
+        assert!(parser::Recipe("version := \"1.0\"").is_err());
+        assert!(parser::Recipe("set shell := [\"bash\", \"-c\"]").is_err());
+        assert!(parser::Recipe("alias b := build").is_err());
+    }
+
+    #[test]
+    fn test_ignore_recipe_body() {
+        assert!(parser::Recipe("    echo hello: world").is_err());
+    }
+
+    #[test]
+    fn test_parse_skips_private_recipes() {
+        let contents = "build:\n\techo\n_helper:\n\techo\n[private]\nhidden:\n\techo\ntest:\n";
+        let recipes = Just.parse(Path::new("justfile"), contents).unwrap();
+        let actual: Vec<(&str, Option<usize>)> = recipes
+            .iter()
+            .map(|target| (target.name(), target.line))
+            .collect();
+        let expected = vec![("build", Some(1)), ("test", Some(8))];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_doc_comments() {
+        let contents =
+            "# Build it\nbuild:\n\techo\n\n# Run the tests\n[no-cd]\ntest:\n\n# stray\n\nlint:\n";
+        let recipes = Just.parse(Path::new("justfile"), contents).unwrap();
+        let actual: Vec<Option<&str>> = recipes
+            .iter()
+            .map(|target| target.description.as_deref())
+            .collect();
+        assert_eq!(actual, vec![Some("Build it"), Some("Run the tests"), None]);
+    }
+}
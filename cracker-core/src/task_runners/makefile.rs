@@ -0,0 +1,992 @@
+use peg::*;
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::{io, process::Command, time};
+
+use crate::task_runners::{Target, TaskRunner};
+use crate::utils::Error;
+use crate::worker::{RunOptions, StdCommand};
+
+parser!(pub grammar parser() for str {
+    #[no_eof]
+    pub rule Targets() -> Vec<String> = a:Target()++ " " Spacing() Separator() !TargetVariable() { a }
+        rule Target() -> String = Spacing() t:$(First() Rest()*) { t.to_string() }
+        rule First() = ['a'..='z'|'A'..='Z'|'0'..='9'|'_'] / Reference()
+        rule Rest() = ['a'..='z'|'A'..='Z'|'0'..='9'|'_'|'.'|'-'|'/'|'+'] / Reference()
+        rule Reference() = "$(" (Reference() / [^'('|')'])* ")" / "${" (Reference() / [^'{'|'}'])* "}"
+        // `foo:` and `foo::`, but not the `:=` and `::=` assignments
+        rule Separator() = "::" !"=" / ":" !"=" !":="
+        rule TargetVariable() = Blank() Modifier()* Name() Blank() Operator()
+    /// Targets and the prerequisites text after the colon, up to an inline
+    /// recipe.
+    #[no_eof]
+    pub rule Rule() -> (Vec<String>, String) = t:Targets() p:$([^';']*) { (t, p.to_string()) }
+    #[no_eof]
+    pub rule Assignment() -> (String, String, String) = Blank() Modifier()* n:$(Name()) Blank() o:$(Operator()) Blank() v:$([_]*) {
+            (n.to_string(), o.to_string(), v.trim_end().to_string())
+        }
+        rule Modifier() = ("export" / "override" / "private") [' '|'\t']+
+        rule Name() = ['a'..='z'|'A'..='Z'|'0'..='9'|'_'|'.'|'-']+
+        rule Operator() = "::=" / ":=" / "?=" / "+=" / "!=" / "="
+        rule Spacing() = quiet!{[' ']*}
+        rule Blank() = quiet!{[' '|'\t']*}
+});
+
+/// How long make gets to print its database, `$(shell)` calls included.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn new(target: String) -> StdCommand {
+    StdCommand::new(target, "make".to_string()).with_runner(&Makefile)
+}
+
+/// Splits a line at the first `#` not escaped with a backslash into the
+/// text before it and the comment after it.
+fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '#' if !escaped => return (&line[..i], Some(&line[i + 1..])),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    (line, None)
+}
+
+/// The `define` or `endef` keyword of a directive line, if any.
+fn define_keyword(line: &str) -> Option<&'static str> {
+    let mut words = split_comment(line)
+        .0
+        .split_whitespace()
+        .skip_while(|word| matches!(*word, "export" | "override" | "private"));
+    match words.next() {
+        Some("define") => Some("define"),
+        Some("endef") => Some("endef"),
+        _ => None,
+    }
+}
+
+/// A line as make reads it, with its comment split off.
+struct LogicalLine {
+    /// Line number the line starts at.
+    number: usize,
+    text: String,
+    comment: Option<String>,
+}
+
+/// Joins backslash continued lines and drops recipes and the bodies of
+/// `define` blocks, leaving the lines rules, variables and includes are
+/// read from.
+fn logical_lines(contents: &str) -> Vec<LogicalLine> {
+    let mut joined = Vec::new();
+    let mut line = String::new();
+    let mut start = 0;
+    for (number, physical) in contents.lines().enumerate() {
+        let physical = if line.is_empty() {
+            start = number + 1;
+            physical
+        } else {
+            physical.trim_start()
+        };
+        let backslashes = physical.len() - physical.trim_end_matches('\\').len();
+        if backslashes % 2 == 1 {
+            line.push_str(physical[..physical.len() - 1].trim_end());
+            line.push(' ');
+        } else {
+            line.push_str(physical);
+            joined.push((start, mem::take(&mut line)));
+        }
+    }
+    if !line.is_empty() {
+        joined.push((start, line));
+    }
+
+    let mut depth = 0;
+    joined
+        .into_iter()
+        .filter(|(_, line)| {
+            if line.starts_with('\t') {
+                return false;
+            }
+            match define_keyword(line) {
+                Some("define") => depth += 1,
+                Some(_) if depth > 0 => depth -= 1,
+                _ => return depth == 0,
+            }
+            false
+        })
+        .map(|(number, line)| {
+            let (text, comment) = split_comment(&line);
+            LogicalLine {
+                number,
+                text: text.to_string(),
+                comment: comment.map(String::from),
+            }
+        })
+        .collect()
+}
+
+/// The file patterns of an `include`, `-include` or `sinclude` line and
+/// whether missing files are fine.
+fn include(line: &str) -> Option<(&str, bool)> {
+    let line = line.trim_start();
+    [("include", false), ("-include", true), ("sinclude", true)]
+        .into_iter()
+        .find_map(|(keyword, optional)| {
+            line.strip_prefix(keyword)
+                .filter(|rest| rest.starts_with([' ', '\t']))
+                .map(|rest| (rest, optional))
+        })
+}
+
+/// The targets of a `.PHONY:` line.
+fn phony(line: &str) -> Option<&str> {
+    line.trim_start()
+        .strip_prefix(".PHONY")?
+        .trim_start()
+        .strip_prefix(':')
+}
+
+/// Matches a file name against a pattern with `*` and `?` wildcards.
+fn wildcard_match(pattern: &[char], name: &[char]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some('*'), _) => {
+            wildcard_match(&pattern[1..], name)
+                || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+        }
+        (Some('?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// Existing files matching `pattern`, sorted per directory like make's
+/// wildcard. Hidden files only match a pattern starting with a dot.
+fn glob(pattern: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let Component::Normal(name) = component else {
+            paths.iter_mut().for_each(|path| path.push(component));
+            continue;
+        };
+        let name = name.to_string_lossy();
+        if !name.contains(['*', '?']) {
+            paths.iter_mut().for_each(|path| path.push(name.as_ref()));
+            continue;
+        }
+        let pattern: Vec<char> = name.chars().collect();
+        paths = paths
+            .iter()
+            .flat_map(|dir| {
+                let dir = if dir.as_os_str().is_empty() {
+                    Path::new(".")
+                } else {
+                    dir
+                };
+                let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| {
+                        let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+                        (name.first() != Some(&'.') || pattern.first() == Some(&'.'))
+                            && wildcard_match(&pattern, &name)
+                    })
+                    .map(|entry| dir.join(entry.file_name()))
+                    .collect();
+                matches.sort();
+                matches
+            })
+            .collect();
+    }
+    paths.retain(|path| path.is_file());
+    paths
+}
+
+/// Expands `$(NAME)` and `${NAME}` references to known variables, anything
+/// else, like function calls, is left as written.
+fn expand(text: &str, variables: &HashMap<String, String>, depth: usize) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let close = match rest[1..].chars().next() {
+            Some('(') => ')',
+            Some('{') => '}',
+            _ => {
+                expanded.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let open = if close == ')' { '(' } else { '{' };
+        let mut nesting = 0;
+        let end = rest.char_indices().skip(1).find(|(_, c)| {
+            if *c == open {
+                nesting += 1;
+            } else if *c == close {
+                nesting -= 1;
+            }
+            nesting == 0
+        });
+        let Some((end, _)) = end else {
+            break;
+        };
+        let reference = &rest[..=end];
+        match variables.get(&rest[2..end]) {
+            Some(value) if depth < 16 => expanded.push_str(&expand(value, variables, depth + 1)),
+            _ => expanded.push_str(reference),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Reads a makefile and the files it includes in the order make does,
+/// expanding variables as they are known at each line.
+struct Reader {
+    /// Directory make runs in, include paths are relative to it.
+    dir: PathBuf,
+    variables: HashMap<String, String>,
+    targets: Vec<Target>,
+    /// Targets declared `.PHONY`, which may come before or after their rule.
+    phony: Vec<String>,
+    /// Files being read, to stop include loops.
+    reading: Vec<PathBuf>,
+}
+
+impl Reader {
+    fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            variables: HashMap::new(),
+            targets: Vec::new(),
+            phony: Vec::new(),
+            reading: Vec::new(),
+        }
+    }
+
+    /// Rules are described by a `## text` comment on the rule line, or else
+    /// by the block of comment lines right above it.
+    fn read(&mut self, file: &Path, contents: &str) {
+        self.reading.push(file.to_path_buf());
+        let mut comments: Vec<String> = Vec::new();
+        for LogicalLine {
+            number,
+            text: line,
+            comment,
+        } in logical_lines(contents)
+        {
+            if line.trim().is_empty() {
+                match comment {
+                    Some(comment) => {
+                        let comment = comment.trim_start_matches('#').trim();
+                        // skip separator lines like `# -----`
+                        if comment.chars().any(char::is_alphanumeric) {
+                            comments.push(comment.to_string());
+                        }
+                    }
+                    None => comments.clear(),
+                }
+                continue;
+            }
+            if let Some((patterns, optional)) = include(&line) {
+                let patterns = expand(patterns, &self.variables, 0);
+                self.include(file, &patterns, optional);
+            } else if let Some(targets) = phony(&line) {
+                let targets = expand(targets, &self.variables, 0);
+                self.phony
+                    .extend(targets.split_whitespace().map(String::from));
+            } else if let Ok((name, operator, value)) = parser::Assignment(&line) {
+                self.assign(name, &operator, value);
+            } else if let Ok((targets, prerequisites)) = parser::Rule(&line) {
+                // static pattern rules, `$(OBJS): %.o: %.c`, have patterns
+                // for prerequisites
+                let prerequisites = prerequisites.rsplit(':').next().unwrap_or_default();
+                let prerequisites: Vec<String> = expand(prerequisites, &self.variables, 0)
+                    .split_whitespace()
+                    .filter(|prerequisite| *prerequisite != "|" && !prerequisite.contains('%'))
+                    .map(String::from)
+                    .collect();
+                let description = comment
+                    .as_deref()
+                    .and_then(|comment| comment.strip_prefix('#'))
+                    .map(|comment| comment.trim().to_string())
+                    .or_else(|| Some(comments.join(" ")))
+                    .filter(|description| !description.is_empty());
+                for target in targets {
+                    for target in expand(&target, &self.variables, 0).split_whitespace() {
+                        self.add(file, number, target, &prerequisites, description.clone());
+                    }
+                }
+            }
+            // Special targets like `.PHONY: build` may sit between a comment
+            // and the rule it describes.
+            if !line.starts_with('.') {
+                comments.clear();
+            }
+        }
+        self.reading.pop();
+    }
+
+    /// Adds a target unless it is known, a repeated rule adds its
+    /// prerequisites and a missing description.
+    fn add(
+        &mut self,
+        file: &Path,
+        line: usize,
+        name: &str,
+        prerequisites: &[String],
+        description: Option<String>,
+    ) {
+        match self.targets.iter_mut().find(|t| t.name() == name) {
+            Some(target) => {
+                for prerequisite in prerequisites {
+                    if !target.prerequisites.contains(prerequisite) {
+                        target.prerequisites.push(prerequisite.clone());
+                    }
+                }
+                if target.description.is_none() {
+                    target.description = description;
+                }
+            }
+            None => {
+                let command = new(name.to_string());
+                let target = Target::new(command, file)
+                    .at_line(line)
+                    .with_prerequisites(prerequisites.to_vec())
+                    .with_description(description);
+                self.targets.push(target);
+            }
+        }
+    }
+
+    fn include(&mut self, from: &Path, patterns: &str, optional: bool) {
+        for pattern in patterns.split_whitespace() {
+            let files = glob(&self.dir.join(pattern));
+            if files.is_empty() && !optional {
+                warn!("{from:?} includes {pattern:?}, which does not exist");
+            }
+            for file in files {
+                if self.reading.contains(&file) {
+                    warn!("{from:?} includes {file:?} again, skipping the loop");
+                    continue;
+                }
+                match fs::read_to_string(&file) {
+                    Ok(contents) => self.read(&file, &contents),
+                    Err(error) => warn!("failed to read {file:?}: {error}"),
+                }
+            }
+        }
+    }
+
+    /// `:=` values are expanded when assigned, `!=` needs a shell so its
+    /// variables stay unknown.
+    fn assign(&mut self, name: String, operator: &str, value: String) {
+        match operator {
+            "=" => {
+                self.variables.insert(name, value);
+            }
+            ":=" | "::=" => {
+                let value = expand(&value, &self.variables, 0);
+                self.variables.insert(name, value);
+            }
+            "?=" => {
+                self.variables.entry(name).or_insert(value);
+            }
+            "+=" => {
+                self.variables
+                    .entry(name)
+                    .and_modify(|old| {
+                        old.push(' ');
+                        old.push_str(&value);
+                    })
+                    .or_insert(value);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Makefile;
+
+impl TaskRunner for Makefile {
+    fn name(&self) -> &'static str {
+        "make"
+    }
+
+    fn files(&self) -> &'static [&'static str] {
+        &["GNUmakefile", "makefile", "Makefile"]
+    }
+
+    fn file_args(&self, file: &Path) -> Vec<String> {
+        vec!["-f".to_string(), file.display().to_string()]
+    }
+
+    fn option_args(&self, options: &RunOptions) -> Option<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(directory) = &options.directory {
+            args.push("-C".to_string());
+            args.push(directory.display().to_string());
+        }
+        if let Some(jobs) = options.jobs {
+            args.push(format!("-j{jobs}"));
+        }
+        if options.keep_going {
+            args.push("-k".to_string());
+        }
+        args.extend(options.variables.iter().cloned());
+        Some(args)
+    }
+
+    /// Targets of `file` and the makefiles it includes, which are read
+    /// relative to the directory of `file`.
+    fn parse(&self, file: &Path, contents: &str) -> Result<Vec<Target>, Error> {
+        parse_in(file, contents, &super::default_dir(file))
+    }
+}
+
+/// Directory the files make reads are relative to, named as `file` is: the
+/// directory of `file` when make runs in it, otherwise `directory`.
+fn read_dir<'a>(file: &'a Path, directory: &'a Path) -> &'a Path {
+    match super::default_dir(file) == directory {
+        true => file.parent().unwrap_or(Path::new("")),
+        false => directory,
+    }
+}
+
+/// Targets of `file` and the makefiles it includes, read relative to
+/// `directory` where make runs, as make itself reads them.
+pub fn parse_in(file: &Path, contents: &str, directory: &Path) -> Result<Vec<Target>, Error> {
+    let mut reader = Reader::new(read_dir(file, directory));
+    reader.read(file, contents);
+    let mut targets = reader.targets;
+    for target in &mut targets {
+        target.phony = reader.phony.iter().any(|name| name == target.name());
+    }
+    Ok(targets)
+}
+
+/// File and line of a `#  recipe to execute (from 'FILE', line N):` comment.
+fn recipe_location(line: &str) -> Option<(&str, usize)> {
+    let (file, line) = line
+        .strip_prefix("#  recipe to execute (from '")?
+        .split_once("', line ")?;
+    Some((file, line.strip_suffix("):")?.parse().ok()?))
+}
+
+/// Targets in the database printed by `make -pRrq -f makefile` run in
+/// `dir`, sorted by name. Targets with a recipe are placed at it, others at
+/// `file`, which is how the caller names `makefile`.
+pub fn parse_database(file: &Path, makefile: &Path, dir: &Path, output: &str) -> Vec<Target> {
+    let Some((_, files)) = output.split_once("\n# Files\n") else {
+        return Vec::new();
+    };
+    let files = files
+        .split("\n# files hash-table stats")
+        .next()
+        .unwrap_or(files);
+
+    let mut targets: Vec<Target> = Vec::new();
+    for entry in files.split("\n\n") {
+        if entry.lines().any(|line| line == "# Not a target:") {
+            continue;
+        }
+        let Some((name, prerequisites)) = entry
+            .lines()
+            .find(|line| !line.is_empty() && !line.starts_with(['#', '\t']))
+            .and_then(|rule| rule.split_once(':'))
+        else {
+            continue;
+        };
+        if name.starts_with('.') || name.contains('%') || targets.iter().any(|t| t.name() == name) {
+            continue;
+        }
+        let prerequisites = prerequisites
+            .strip_prefix(':')
+            .unwrap_or(prerequisites)
+            .split_whitespace()
+            .filter(|prerequisite| *prerequisite != "|")
+            .map(String::from)
+            .collect();
+        let mut target = match entry.lines().find_map(recipe_location) {
+            Some((recipe_file, line)) => {
+                let recipe_file = match Path::new(recipe_file) == makefile {
+                    true => file.to_path_buf(),
+                    false => dir.join(recipe_file),
+                };
+                Target::new(new(name.to_string()), recipe_file).at_line(line)
+            }
+            None => Target::new(new(name.to_string()), file),
+        };
+        target.phony = entry
+            .lines()
+            .any(|line| line.starts_with("#  Phony target"));
+        targets.push(target.with_prerequisites(prerequisites));
+    }
+    targets.sort_by(|a, b| a.name().cmp(b.name()));
+    targets
+}
+
+/// Asks make for the targets of `file` with `make -pRrq`, which evaluates
+/// the makefiles without running any recipe. Make runs in `directory`, where
+/// the targets run, so includes and variables resolve as they will then.
+pub async fn database(file: &Path, directory: &Path) -> Result<Vec<Target>, Error> {
+    let name = file
+        .file_name()
+        .ok_or(Error::IoError(io::ErrorKind::InvalidInput))?;
+    // files make names are relative to the directory of `file`, as `file` is,
+    // unless make runs elsewhere
+    let dir = read_dir(file, directory);
+    let makefile = match super::default_dir(file) == directory {
+        true => PathBuf::from(name),
+        false => std::path::absolute(file).map_err(|error| Error::IoError(error.kind()))?,
+    };
+    let output = Command::new("make")
+        .arg("-pRrq")
+        .arg("-f")
+        .arg(&makefile)
+        .arg(":")
+        .current_dir(directory)
+        .env("LC_ALL", "C")
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = time::timeout(DATABASE_TIMEOUT, output)
+        .await
+        .map_err(|_| Error::IoError(io::ErrorKind::TimedOut))?
+        .map_err(|error| Error::IoError(error.kind()))?;
+
+    // `:` is not a target, so make exits with an error after printing.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !stdout.contains("\n# Files\n") {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Parse(stderr.trim().to_string()));
+    }
+    Ok(parse_database(file, &makefile, dir, &stdout))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_runners::{load, Discovery};
+
+    #[test]
+    fn test_one_target_rule() {
+        let rule = "target:";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("target")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_multi_target_rule() {
+        let rule = "target t target_three:";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![
+            String::from("target"),
+            String::from("t"),
+            String::from("target_three"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_multi_target_variable_spacing_rule() {
+        let rule = " target  t       target_three:";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![
+            String::from("target"),
+            String::from("t"),
+            String::from("target_three"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_targets_with_num() {
+        let rule = "1target target1:";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("1target"), String::from("target1")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_target_with_dot() {
+        let rule = "target.o:";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("target.o")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_target_inline_recipe_rule() {
+        let rule = "target: ; tail -f /dev/null";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("target")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_target_with_hyphen_and_slash() {
+        let rule = "build-docker test/unit:";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("build-docker"), String::from("test/unit")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_target_with_variable() {
+        let rule = "$(BIN) ${OUT}/$(notdir $(SRC)):";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![
+            String::from("$(BIN)"),
+            String::from("${OUT}/$(notdir $(SRC))"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_double_colon_rule() {
+        let rule = "target:: prerequisite";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("target")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_space_before_colon() {
+        let rule = "target t :";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("target"), String::from("t")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ignore_double_colon_variable() {
+        let rule = "files ::= file1 file2";
+        let actual = parser::Targets(rule).ok();
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ignore_target_specific_variable() {
+        let rule = "target: override CFLAGS += -g";
+        let actual = parser::Targets(rule).ok();
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ignore_phony() {
+        let rule = ".PHONY: target1 target2";
+        let actual = parser::Targets(rule).ok();
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_guard_target() {
+        let rule = "_guard: target1 target2";
+        let actual: Vec<String> = parser::Targets(rule).ok().unwrap();
+        let expected: Vec<String> = vec![String::from("_guard")];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ignore_variable() {
+        let rule = "files := file1 file2";
+        let actual = parser::Targets(rule).ok();
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ignore_wildcard_catchall() {
+        let rule = "%::";
+        let actual = parser::Targets(rule).ok();
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_ignore_tabbed_target() {
+        let rule = "\ttarget:";
+        let actual = parser::Targets(rule).ok();
+        let expected = None;
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_recipe_should_err() {
+        let rule = "\t@echo \"Hello Cracker\"";
+        assert!(parser::Targets(rule).is_err());
+    }
+
+    fn parse(contents: &str) -> Vec<String> {
+        Makefile
+            .parse(Path::new("Makefile"), contents)
+            .unwrap()
+            .iter()
+            .map(|target| target.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_line_continuation() {
+        let contents = "one \\\n    two: dep1 \\\n\tdep2\nthree:\n";
+        assert_eq!(parse(contents), vec!["one", "two", "three"]);
+    }
+
+    #[test]
+    fn test_parse_skips_continued_recipe() {
+        let contents = "build:\n\techo one \\\nfake: two\ntest:\n";
+        assert_eq!(parse(contents), vec!["build", "test"]);
+    }
+
+    #[test]
+    fn test_parse_skips_define_block() {
+        let contents = "define RULE =\nfake:\n\techo fake\nendef\nreal:\n";
+        assert_eq!(parse(contents), vec!["real"]);
+    }
+
+    #[test]
+    fn test_parse_skips_comments() {
+        let contents = "build: # run: the build\n# test:\n";
+        assert_eq!(parse(contents), vec!["build"]);
+    }
+
+    #[test]
+    fn test_parse_expands_variables() {
+        let contents = "\
+BIN = ck
+TOOLS := tool1
+TOOLS += tool2
+OUT ?= out
+OUT ?= other
+$(BIN) $(TOOLS): main.o
+${OUT}/$(BIN):
+$(BIN)::
+$(UNKNOWN):
+";
+        assert_eq!(
+            parse(contents),
+            vec!["ck", "tool1", "tool2", "out/ck", "$(UNKNOWN)"]
+        );
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        let chars = |s: &str| s.chars().collect::<Vec<char>>();
+        assert!(wildcard_match(&chars("*.mk"), &chars("build.mk")));
+        assert!(wildcard_match(&chars("?.mk"), &chars("a.mk")));
+        assert!(wildcard_match(&chars("*"), &chars("")));
+        assert!(!wildcard_match(&chars("*.mk"), &chars("build.mk.bak")));
+        assert!(!wildcard_match(&chars("?.mk"), &chars("ab.mk")));
+    }
+
+    #[tokio::test]
+    async fn test_load_follows_includes() {
+        let group = load(
+            &Makefile,
+            PathBuf::from("tests/test_files/include/Makefile"),
+            Discovery::Parse,
+        )
+        .await
+        .unwrap();
+        let actual: Vec<(&str, Option<String>)> = group
+            .targets
+            .iter()
+            .map(|target| (target.name(), group.source(target)))
+            .collect();
+        let expected = vec![
+            ("build", Some(String::from("mk/build.mk:1"))),
+            ("build-docker", Some(String::from("mk/docker.mk:3"))),
+            ("loop", Some(String::from("mk/loop.mk:2"))),
+            ("all", None),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_database() {
+        let output = std::fs::read_to_string("tests/test_files/make_database.txt").unwrap();
+        let targets = parse_database(
+            Path::new("project/Makefile"),
+            Path::new("Makefile"),
+            Path::new("project"),
+            &output,
+        );
+        let actual: Vec<(&str, Vec<String>, &Path, Option<usize>)> = targets
+            .iter()
+            .map(|t| (t.name(), t.prerequisites.clone(), t.file.as_path(), t.line))
+            .collect();
+        let expected = vec![
+            ("_guard", vec![], Path::new("project/Makefile"), None),
+            (
+                "all",
+                vec![String::from("build"), String::from("ck")],
+                Path::new("project/Makefile"),
+                None,
+            ),
+            ("build", vec![], Path::new("project/mk/build.mk"), Some(2)),
+            (
+                "ck",
+                vec![String::from("main.o")],
+                Path::new("project/Makefile"),
+                Some(8),
+            ),
+            (
+                "test",
+                vec![String::from("build")],
+                Path::new("project/mk/build.mk"),
+                Some(5),
+            ),
+        ];
+        assert_eq!(actual, expected);
+        let phony: Vec<&str> = targets
+            .iter()
+            .filter(|t| t.phony)
+            .map(Target::name)
+            .collect();
+        assert_eq!(phony, vec!["all"]);
+    }
+
+    #[test]
+    fn test_parse_database_without_files() {
+        let file = Path::new("Makefile");
+        assert!(parse_database(file, file, Path::new(""), "make: not a database").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_database() {
+        let file = Path::new("tests/test_files/project/Makefile");
+        let targets = database(file, &super::super::default_dir(file))
+            .await
+            .unwrap();
+        let actual: Vec<(&str, Option<usize>)> =
+            targets.iter().map(|t| (t.name(), t.line)).collect();
+        assert_eq!(actual, vec![("build", Some(4))]);
+    }
+
+    fn descriptions(contents: &str) -> Vec<(String, Option<String>)> {
+        Makefile
+            .parse(Path::new("Makefile"), contents)
+            .unwrap()
+            .into_iter()
+            .map(|target| (target.name().to_string(), target.description))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_inline_description() {
+        let contents = "build: deps ## Build the app\ntest: # not a description\n";
+        let expected = vec![
+            (String::from("build"), Some(String::from("Build the app"))),
+            (String::from("test"), None),
+        ];
+        assert_eq!(descriptions(contents), expected);
+    }
+
+    #[test]
+    fn test_parse_comment_block_description() {
+        let contents = "\
+# ----------
+# Run the tests,
+# all of them.
+.PHONY: test
+test:
+
+# Unrelated comment
+
+lint:
+VAR = 1
+# Deploy
+deploy: ## Deploy to staging
+";
+        let expected = vec![
+            (
+                String::from("test"),
+                Some(String::from("Run the tests, all of them.")),
+            ),
+            (String::from("lint"), None),
+            (
+                String::from("deploy"),
+                Some(String::from("Deploy to staging")),
+            ),
+        ];
+        assert_eq!(descriptions(contents), expected);
+    }
+
+    #[test]
+    fn test_parse_phony() {
+        let contents = "\
+PHONY := lint
+.PHONY: build $(PHONY)
+build:
+a.o:
+lint:
+test:
+.PHONY: test
+";
+        let targets = Makefile.parse(Path::new("Makefile"), contents).unwrap();
+        let actual: Vec<(&str, bool)> = targets.iter().map(|t| (t.name(), t.phony)).collect();
+        let expected = vec![
+            ("build", true),
+            ("a.o", false),
+            ("lint", true),
+            ("test", true),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_rule_prerequisites() {
+        let rule = "target: dep1 dep2 | order ; echo inline";
+        let actual = parser::Rule(rule).ok().unwrap();
+        let expected = (
+            vec![String::from("target")],
+            String::from(" dep1 dep2 | order "),
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_prerequisites() {
+        let contents = "\
+OBJS = main.o util.o
+all: build test
+build: $(OBJS) | out
+$(OBJS): %.o: %.c
+build: config.h
+test:: build
+";
+        let targets = Makefile.parse(Path::new("Makefile"), contents).unwrap();
+        let actual: Vec<(&str, Vec<&str>)> = targets
+            .iter()
+            .map(|t| {
+                (
+                    t.name(),
+                    t.prerequisites.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        let expected = vec![
+            ("all", vec!["build", "test"]),
+            ("build", vec!["main.o", "util.o", "out", "config.h"]),
+            ("main.o", vec![]),
+            ("util.o", vec![]),
+            ("test", vec!["build"]),
+        ];
+        assert_eq!(actual, expected);
+    }
+}
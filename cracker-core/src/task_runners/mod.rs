@@ -0,0 +1,490 @@
+pub mod cargo;
+pub mod just;
+pub mod makefile;
+pub mod npm;
+
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+
+use crate::utils::{async_read_lines, Error};
+use crate::worker::{RunOptions, StdCommand};
+
+/// A build tool ck knows how to list and run targets for.
+pub trait TaskRunner: Debug + Sync {
+    /// Name the targets are grouped under.
+    fn name(&self) -> &'static str;
+
+    /// Build file names this runner is looked up by, in the order the tool
+    /// itself prefers them.
+    fn files(&self) -> &'static [&'static str];
+
+    /// Targets declared in `contents`, the text of `file`, with the command
+    /// that runs each.
+    fn parse(&self, file: &Path, contents: &str) -> Result<Vec<Target>, Error>;
+
+    /// Arguments telling the tool which build file to read, for tools that
+    /// would otherwise look it up themselves in the working directory.
+    fn file_args(&self, _file: &Path) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Arguments ahead of the target for the variables, jobs, keep going and
+    /// directory of `options`, `None` for tools that have no such options.
+    fn option_args(&self, _options: &RunOptions) -> Option<Vec<String>> {
+        None
+    }
+
+    /// First of [`TaskRunner::files`] present in `dir`.
+    fn discover(&self, dir: &Path) -> Option<PathBuf> {
+        self.files()
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|file| file.is_file())
+    }
+}
+
+/// Every supported runner, in the order groups are shown.
+pub static RUNNERS: [&dyn TaskRunner; 4] =
+    [&makefile::Makefile, &npm::Npm, &just::Just, &cargo::Cargo];
+
+/// Directory a build file's targets run in by default, the one it is in.
+fn default_dir(file: &Path) -> PathBuf {
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf())
+}
+
+/// Runner for a build file given by name, make for anything unrecognised.
+pub fn runner_for(file: &Path) -> &'static dyn TaskRunner {
+    let name = file
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    RUNNERS
+        .iter()
+        .find(|runner| runner.files().contains(&name))
+        .copied()
+        .unwrap_or(&makefile::Makefile)
+}
+
+/// Extensions of compiled objects and libraries make builds as files.
+const OBJECT_EXTENSIONS: [&str; 11] = [
+    "o", "obj", "a", "lo", "la", "so", "dylib", "dll", "lib", "gch", "d",
+];
+
+/// A runnable target and where it is declared.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub command: StdCommand,
+    /// Build file declaring the target, an included file for make.
+    pub file: PathBuf,
+    /// Line of the declaration, counting from 1, when the runner knows it.
+    pub line: Option<usize>,
+    /// Targets this one depends on, when the runner knows them.
+    pub prerequisites: Vec<String>,
+    /// What the target does, from the comments of the build file.
+    pub description: Option<String>,
+    /// Runs a command rather than making a file of its name. Make targets
+    /// are phony only when declared `.PHONY`.
+    pub phony: bool,
+}
+
+impl Target {
+    pub fn new(command: StdCommand, file: impl Into<PathBuf>) -> Self {
+        Self {
+            command,
+            file: file.into(),
+            line: None,
+            prerequisites: Vec::new(),
+            description: None,
+            phony: true,
+        }
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+
+    pub fn with_prerequisites(mut self, prerequisites: Vec<String>) -> Self {
+        self.prerequisites = prerequisites;
+        self
+    }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        self.command.target()
+    }
+
+    /// Internal targets, by convention named with a leading `_` or `.`.
+    pub fn hidden(&self) -> bool {
+        self.name().starts_with(['_', '.'])
+    }
+
+    /// File targets making compiled objects or libraries, like `main.o`.
+    pub fn object_file(&self) -> bool {
+        !self.phony
+            && Path::new(self.name())
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| OBJECT_EXTENSIONS.contains(&extension))
+    }
+}
+
+/// Which targets a target list shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetFilter {
+    pub phony_only: bool,
+    pub hide_hidden: bool,
+    pub hide_object_files: bool,
+}
+
+impl Default for TargetFilter {
+    fn default() -> Self {
+        Self {
+            phony_only: false,
+            hide_hidden: true,
+            hide_object_files: true,
+        }
+    }
+}
+
+impl TargetFilter {
+    pub fn shows(&self, target: &Target) -> bool {
+        !(self.phony_only && !target.phony
+            || self.hide_hidden && target.hidden()
+            || self.hide_object_files && target.object_file())
+    }
+}
+
+/// How make targets are found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Discovery {
+    /// Read the makefiles with ck's own parser.
+    #[default]
+    Parse,
+    /// Ask make for its database, falling back to the parser when make is
+    /// unavailable or fails.
+    Database,
+}
+
+/// Targets of one build file.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub runner: &'static str,
+    pub file: PathBuf,
+    /// Directory the targets run in.
+    pub dir: PathBuf,
+    pub targets: Vec<Target>,
+}
+
+impl Group {
+    /// Runs the targets in `dir`, telling the runner where the build file
+    /// is when it would not find it there.
+    fn in_dir(mut self, runner: &dyn TaskRunner, dir: PathBuf) -> Self {
+        let file = if dir == default_dir(&self.file) {
+            self.file.file_name().map(PathBuf::from).unwrap_or_default()
+        } else {
+            std::path::absolute(&self.file).unwrap_or_else(|_| self.file.clone())
+        };
+        let file_args = runner.file_args(&file);
+        for target in &mut self.targets {
+            let args = [file_args.as_slice(), target.command.args()].concat();
+            target.command = target.command.clone().with_args(args).in_dir(dir.clone());
+        }
+        self.dir = dir;
+        self
+    }
+
+    /// Position and target named `name`.
+    pub fn find(&self, name: &str) -> Option<(usize, &Target)> {
+        self.targets
+            .iter()
+            .enumerate()
+            .find(|(_, t)| t.name() == name)
+    }
+
+    /// Targets that have `name` as a prerequisite, with their positions.
+    pub fn dependents<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (usize, &'a Target)> {
+        self.targets
+            .iter()
+            .enumerate()
+            .filter(move |(_, t)| t.prerequisites.iter().any(|p| p == name))
+    }
+
+    /// Where `target` is declared, relative to the build file's directory,
+    /// when that is not the build file itself.
+    pub fn source(&self, target: &Target) -> Option<String> {
+        if target.file == self.file {
+            return None;
+        }
+        let dir = self.file.parent().unwrap_or(Path::new(""));
+        let file = target.file.strip_prefix(dir).unwrap_or(&target.file);
+        Some(match target.line {
+            Some(line) => format!("{}:{line}", file.display()),
+            None => file.display().to_string(),
+        })
+    }
+}
+
+pub async fn load(
+    runner: &'static dyn TaskRunner,
+    file: PathBuf,
+    discovery: Discovery,
+) -> Result<Group, Error> {
+    load_in(runner, file, discovery, None).await
+}
+
+/// Loads `file` with its targets running in `directory`, or the directory
+/// of `file` when not given.
+async fn load_in(
+    runner: &'static dyn TaskRunner,
+    file: PathBuf,
+    discovery: Discovery,
+    directory: Option<PathBuf>,
+) -> Result<Group, Error> {
+    let contents = async_read_lines(file.clone()).await?;
+    let dir = directory.unwrap_or_else(|| default_dir(&file));
+    let is_make = runner.name() == makefile::Makefile.name();
+    // make reads includes from where it runs
+    let parse = || match is_make {
+        true => makefile::parse_in(&file, &contents, &dir),
+        false => runner.parse(&file, &contents),
+    };
+    let database = match discovery {
+        Discovery::Database if is_make => makefile::database(&file, &dir)
+            .await
+            .inspect_err(|error| info!("no make database for {file:?}: {error:?}"))
+            .ok(),
+        _ => None,
+    };
+    let targets = match database {
+        // make's database has no comments, the parser knows the descriptions
+        Some(mut targets) => {
+            if let Ok(parsed) = parse() {
+                for target in &mut targets {
+                    target.description = parsed
+                        .iter()
+                        .find(|p| p.name() == target.name())
+                        .and_then(|p| p.description.clone());
+                }
+            }
+            targets
+        }
+        None => parse()?,
+    };
+    debug!("{} targets found in {file:?}", targets.len());
+
+    let group = Group {
+        runner: runner.name(),
+        file,
+        dir: PathBuf::new(),
+        targets,
+    };
+    Ok(group.in_dir(runner, dir))
+}
+
+/// Targets of `file`, or of every supported build file in `directory`
+/// when no file is given. Targets run in `directory` when it is given,
+/// otherwise in the directory of their build file.
+pub async fn groups(
+    file: Option<PathBuf>,
+    discovery: Discovery,
+    directory: Option<PathBuf>,
+) -> Result<Vec<Group>, Error> {
+    match (file, directory) {
+        (Some(file), directory) => Ok(vec![
+            load_in(runner_for(&file), file, discovery, directory).await?,
+        ]),
+        (None, directory) => Ok(discover(directory.unwrap_or_default(), discovery).await),
+    }
+}
+
+/// Groups for every supported build file found in `dir`.
+pub async fn discover(dir: PathBuf, discovery: Discovery) -> Vec<Group> {
+    let mut groups = Vec::new();
+    for runner in RUNNERS {
+        let Some(file) = runner.discover(&dir) else {
+            continue;
+        };
+        match load(runner, file.clone(), discovery).await {
+            Ok(group) => groups.push(group),
+            Err(error) => warn!("failed to load {file:?}: {error:?}"),
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runner_for() {
+        assert_eq!(runner_for(Path::new("a/package.json")).name(), "npm");
+        assert_eq!(runner_for(Path::new("justfile")).name(), "just");
+        assert_eq!(runner_for(Path::new("Cargo.toml")).name(), "cargo");
+        assert_eq!(runner_for(Path::new("GNUmakefile")).name(), "make");
+        assert_eq!(runner_for(Path::new("Makefile.tst")).name(), "make");
+    }
+
+    #[tokio::test]
+    async fn test_discover_groups_by_runner() {
+        let groups = discover(PathBuf::from("tests/test_files/project"), Discovery::Parse).await;
+        let actual: Vec<(&str, Vec<String>)> = groups
+            .iter()
+            .map(|g| {
+                (
+                    g.runner,
+                    g.targets.iter().map(|t| t.command.command_line()).collect(),
+                )
+            })
+            .collect();
+        let expected = vec![
+            ("make", vec![String::from("make -f Makefile build")]),
+            ("npm", vec![String::from("npm run lint")]),
+            ("just", vec![String::from("just deploy")]),
+            (
+                "cargo",
+                cargo::SUBCOMMANDS
+                    .iter()
+                    .map(|s| format!("cargo {s}"))
+                    .collect(),
+            ),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_discover_missing_dir() {
+        let groups = discover(PathBuf::from("tests/test_files/missing"), Discovery::Parse).await;
+        assert!(groups.is_empty());
+    }
+
+    #[test]
+    fn test_target_filter() {
+        let target = |name: &str, phony: bool| {
+            let mut target = Target::new(makefile::new(name.to_string()), "Makefile");
+            target.phony = phony;
+            target
+        };
+        let targets = [
+            target("build", true),
+            target("_guard", true),
+            target("main.o", false),
+            target("ck", false),
+        ];
+        let shown = |filter: TargetFilter| -> Vec<&str> {
+            targets
+                .iter()
+                .filter(|t| filter.shows(t))
+                .map(Target::name)
+                .collect()
+        };
+        assert_eq!(shown(TargetFilter::default()), vec!["build", "ck"]);
+        let filter = TargetFilter {
+            phony_only: true,
+            ..TargetFilter::default()
+        };
+        assert_eq!(shown(filter), vec!["build"]);
+        let filter = TargetFilter {
+            phony_only: false,
+            hide_hidden: false,
+            hide_object_files: false,
+        };
+        assert_eq!(shown(filter), vec!["build", "_guard", "main.o", "ck"]);
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let target = |name: &str, prerequisites: &[&str]| {
+            Target::new(makefile::new(name.to_string()), "Makefile")
+                .with_prerequisites(prerequisites.iter().map(|p| p.to_string()).collect())
+        };
+        let group = Group {
+            runner: "make",
+            file: PathBuf::from("Makefile"),
+            dir: PathBuf::from("."),
+            targets: vec![
+                target("all", &["build", "test"]),
+                target("build", &["main.c"]),
+                target("test", &["build"]),
+            ],
+        };
+        assert_eq!(group.find("test").map(|(i, _)| i), Some(2));
+        assert!(group.find("main.c").is_none());
+        let dependents: Vec<usize> = group.dependents("build").map(|(i, _)| i).collect();
+        assert_eq!(dependents, vec![0, 2]);
+        assert_eq!(group.dependents("all").count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_working_dir() {
+        let file = PathBuf::from("tests/test_files/project/Makefile");
+        let dir = std::path::absolute("tests/test_files/project").unwrap();
+        let group = load(&makefile::Makefile, file.clone(), Discovery::Parse)
+            .await
+            .unwrap();
+        let command = &group.targets[0].command;
+        assert_eq!(group.dir, dir);
+        assert_eq!(command.dir(), Some(dir.as_path()));
+        assert_eq!(command.command_line(), "make -f Makefile build");
+
+        let groups = groups(Some(file), Discovery::Parse, Some(PathBuf::from("/tmp")))
+            .await
+            .unwrap();
+        let command = &groups[0].targets[0].command;
+        assert_eq!(command.dir(), Some(Path::new("/tmp")));
+        assert_eq!(
+            command.command_line(),
+            format!("make -f {}/Makefile build", dir.display())
+        );
+
+        let groups = discover(PathBuf::from("tests/test_files/project"), Discovery::Parse).await;
+        let npm = &groups[1].targets[0].command;
+        assert_eq!(npm.dir(), Some(dir.as_path()));
+        assert_eq!(npm.command_line(), "npm run lint");
+    }
+
+    #[tokio::test]
+    async fn test_includes_in_working_dir() {
+        // make lists the targets from where they run, includes resolve there
+        let tmp = std::env::temp_dir().join(format!("ck-working-dir-{}", std::process::id()));
+        let (project, run) = (tmp.join("project"), tmp.join("run"));
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&run).unwrap();
+        let makefile = project.join("Makefile");
+        std::fs::write(&makefile, "-include local.mk\nbuild:\n\t@echo build\n").unwrap();
+        std::fs::write(run.join("local.mk"), "local:\n\t@echo local\n").unwrap();
+        let mut found = Vec::new();
+        for discovery in [Discovery::Parse, Discovery::Database] {
+            found.push(groups(Some(makefile.clone()), discovery, Some(run.clone())).await);
+        }
+        let _ = std::fs::remove_dir_all(&tmp);
+        for groups in found {
+            let groups = groups.unwrap();
+            let mut targets: Vec<(&str, &Path)> = groups[0]
+                .targets
+                .iter()
+                .map(|t| (t.name(), t.file.as_path()))
+                .collect();
+            targets.sort();
+            assert_eq!(
+                targets,
+                vec![
+                    ("build", makefile.as_path()),
+                    ("local", run.join("local.mk").as_path())
+                ]
+            );
+            for target in &groups[0].targets {
+                assert_eq!(target.command.dir(), Some(run.as_path()));
+            }
+        }
+    }
+}
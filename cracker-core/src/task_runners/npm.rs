@@ -0,0 +1,173 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::task_runners::{Target, TaskRunner};
+use crate::utils::Error;
+use crate::worker::StdCommand;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Npm,
+    Pnpm,
+    Yarn,
+}
+
+impl PackageManager {
+    pub fn command(&self) -> &'static str {
+        match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Yarn => "yarn",
+        }
+    }
+
+    /// The `packageManager` field of package.json wins, otherwise the
+    /// lockfile next to it decides, falling back to npm.
+    pub fn detect(dir: &Path, package: &Value) -> Self {
+        if let Some(field) = package.get("packageManager").and_then(Value::as_str) {
+            match field.split('@').next() {
+                Some("pnpm") => return PackageManager::Pnpm,
+                Some("yarn") => return PackageManager::Yarn,
+                Some("npm") => return PackageManager::Npm,
+                _ => {}
+            }
+        }
+        for (lockfile, manager) in [
+            ("pnpm-lock.yaml", PackageManager::Pnpm),
+            ("yarn.lock", PackageManager::Yarn),
+        ] {
+            if dir.join(lockfile).is_file() {
+                return manager;
+            }
+        }
+        PackageManager::Npm
+    }
+}
+
+/// Script names from the `scripts` table, in the order they are written.
+pub fn scripts(package: &Value) -> Vec<String> {
+    package
+        .get("scripts")
+        .and_then(Value::as_object)
+        .map(|scripts| scripts.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn new(manager: PackageManager, script: String) -> StdCommand {
+    StdCommand::new(script, manager.command().to_string())
+        .with_args(vec!["run".to_string()])
+        .with_runner(&Npm)
+}
+
+#[derive(Debug)]
+pub struct Npm;
+
+impl TaskRunner for Npm {
+    fn name(&self) -> &'static str {
+        "npm"
+    }
+
+    fn files(&self) -> &'static [&'static str] {
+        &["package.json"]
+    }
+
+    fn parse(&self, file: &Path, contents: &str) -> Result<Vec<Target>, Error> {
+        let package: Value =
+            serde_json::from_str(contents).map_err(|error| Error::Parse(error.to_string()))?;
+        let dir = file.parent().unwrap_or(Path::new(""));
+        let manager = PackageManager::detect(dir, &package);
+        debug!("package.json ({file:?}) uses {manager:?}");
+
+        Ok(scripts(&package)
+            .into_iter()
+            .map(|script| Target::new(new(manager, script), file))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_runners::{load, Discovery};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_scripts_keep_order() {
+        let package = serde_json::json!({
+            "name": "app",
+            "scripts": {"test": "jest", "build": "tsc", "lint": "eslint ."}
+        });
+        let expected: Vec<String> = vec![
+            String::from("test"),
+            String::from("build"),
+            String::from("lint"),
+        ];
+        assert_eq!(scripts(&package), expected);
+    }
+
+    #[test]
+    fn test_no_scripts() {
+        let package = serde_json::json!({"name": "app"});
+        assert!(scripts(&package).is_empty());
+    }
+
+    #[test]
+    fn test_detect_npm() {
+        let package = serde_json::json!({});
+        let actual = PackageManager::detect(Path::new("tests/test_files/npm"), &package);
+        assert_eq!(actual, PackageManager::Npm);
+    }
+
+    #[test]
+    fn test_detect_pnpm_lockfile() {
+        let package = serde_json::json!({});
+        let actual = PackageManager::detect(Path::new("tests/test_files/pnpm"), &package);
+        assert_eq!(actual, PackageManager::Pnpm);
+    }
+
+    #[test]
+    fn test_detect_yarn_lockfile() {
+        let package = serde_json::json!({});
+        let actual = PackageManager::detect(Path::new("tests/test_files/yarn"), &package);
+        assert_eq!(actual, PackageManager::Yarn);
+    }
+
+    #[test]
+    fn test_detect_package_manager_field() {
+        let package = serde_json::json!({"packageManager": "pnpm@9.1.0"});
+        let actual = PackageManager::detect(Path::new("tests/test_files/yarn"), &package);
+        assert_eq!(actual, PackageManager::Pnpm);
+    }
+
+    #[tokio::test]
+    async fn test_load() {
+        let actual: Vec<String> = load(
+            &Npm,
+            PathBuf::from("tests/test_files/yarn/package.json"),
+            Discovery::Parse,
+        )
+        .await
+        .unwrap()
+        .targets
+        .iter()
+        .map(|target| target.command.command_line())
+        .collect();
+        let expected: Vec<String> = vec![
+            String::from("yarn run build"),
+            String::from("yarn run test"),
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_load_invalid_json() {
+        let result = load(
+            &Npm,
+            PathBuf::from("tests/test_files/single_line.txt"),
+            Discovery::Parse,
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Parse(_))));
+    }
+}
@@ -0,0 +1,173 @@
+use std::env;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{fs, io};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    IoError(io::ErrorKind),
+    Parse(String),
+}
+
+pub async fn async_read_lines<P>(filename: P) -> Result<Arc<String>, Error>
+where
+    P: AsRef<Path> + Debug + Clone,
+{
+    let contents = fs::read_to_string(filename.clone())
+        .await
+        .map(Arc::new)
+        .map_err(|error| Error::IoError(error.kind()))?;
+    debug!("file ({:?}) loaded", filename.clone());
+
+    Ok(contents)
+}
+
+/// Short human readable elapsed time, e.g. `850ms`, `12.3s` or `4m 05s`.
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else if secs >= 1 {
+        format!("{:.1}s", duration.as_secs_f64())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+/// `$XDG_<KIND>_HOME/cracker`, or `~/<fallback>/cracker` when it is not set.
+fn xdg_dir(kind: &str, fallback: &str) -> Option<PathBuf> {
+    let base = env::var_os(format!("XDG_{kind}_HOME"))
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(fallback)))?;
+    Some(base.join("cracker"))
+}
+
+/// Where ck keeps data between sessions, like the task history.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("DATA", ".local/share")
+}
+
+/// Where ck's user settings are.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("CONFIG", ".config")
+}
+
+/// Splits `text` into arguments on whitespace, keeping single or double
+/// quoted text together the way a shell does.
+pub fn split_args(text: &str) -> Result<Vec<String>, Error> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut quote = None;
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => arg.get_or_insert_with(String::new).push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                arg.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => args.extend(arg.take()),
+            None => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(Error::Parse(format!("unterminated quote in: {text}")));
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
+/// `arg` as typed in a shell, single quoted when it is empty or has
+/// characters the shell would treat specially.
+pub fn quote(arg: &str) -> String {
+    let plain = |c: char| c.is_alphanumeric() || "-_=+./:,@%".contains(c);
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_not_found() {
+        let result: Result<Arc<String>, Error> = async_read_lines("non-existent.file").await;
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), Error::IoError(ErrorKind::NotFound));
+    }
+
+    #[tokio::test]
+    async fn test_empty_file() {
+        let result: Result<Arc<String>, Error> =
+            async_read_lines("tests/test_files/empty.txt").await;
+        assert!(result.is_ok());
+
+        let content: Arc<String> = result.unwrap();
+        assert!(content.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_single_line_file() {
+        let result: Result<Arc<String>, Error> =
+            async_read_lines("tests/test_files/single_line.txt").await;
+        assert!(result.is_ok());
+        let actual: Arc<String> = result.unwrap();
+        let expected: Arc<String> = Arc::new("Hello World".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_multiple_lines_file() {
+        let result: Result<Arc<String>, Error> =
+            async_read_lines("tests/test_files/multiple_lines.txt").await;
+        assert!(result.is_ok());
+        let actual: Arc<String> = result.unwrap();
+        let expected: Arc<String> = Arc::new("Line 1\nLine 2\nLine 3".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_file_with_empty_lines() {
+        let result: Result<Arc<String>, Error> =
+            async_read_lines("tests/test_files/empty_lines.txt").await;
+        assert!(result.is_ok());
+        let actual: Arc<String> = result.unwrap();
+        let expected: Arc<String> = Arc::new("First\n\n\nLast".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(12_340)), "12.3s");
+        assert_eq!(format_duration(Duration::from_secs(245)), "4m 05s");
+        assert_eq!(format_duration(Duration::from_secs(7_500)), "2h 05m");
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#"  CFLAGS="-O2 -g" NAME='a b' --verbose "" "#).unwrap(),
+            vec!["CFLAGS=-O2 -g", "NAME=a b", "--verbose", ""]
+        );
+        assert!(split_args("NAME='a b").is_err());
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("CFLAGS=-O2"), "CFLAGS=-O2");
+        assert_eq!(quote("CFLAGS=-O2 -g"), "'CFLAGS=-O2 -g'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+    }
+}
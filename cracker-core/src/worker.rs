@@ -0,0 +1,891 @@
+use futures::channel::mpsc;
+use futures::stream::BoxStream;
+use futures::{stream, Future, SinkExt, Stream, StreamExt};
+
+use nix::sys::signal::{killpg, Signal};
+use nix::unistd::Pid;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::time::{self, Duration, Instant};
+
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::pty;
+use crate::task_runners::TaskRunner;
+use crate::utils::quote;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopSignal {
+    Interrupt,
+    Terminate,
+}
+
+impl StopSignal {
+    fn signal(&self) -> Signal {
+        match self {
+            StopSignal::Interrupt => Signal::SIGINT,
+            StopSignal::Terminate => Signal::SIGTERM,
+        }
+    }
+}
+
+/// How a running task is asked to stop: `signal` is sent to the whole
+/// process group, and anything still alive after `grace_period` is killed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopPolicy {
+    pub signal: StopSignal,
+    pub grace_period: Duration,
+}
+
+impl Default for StopPolicy {
+    fn default() -> Self {
+        Self {
+            signal: StopSignal::Interrupt,
+            grace_period: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Options of a single run, given to the command around its target. The
+/// variables, jobs, keep going and directory are make's, runners that take
+/// them turn them into arguments with [`TaskRunner::option_args`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunOptions {
+    /// `VAR=value` overrides.
+    pub variables: Vec<String>,
+    /// `-j N`, jobs run at once.
+    pub jobs: Option<usize>,
+    /// `-k`, keep going after an error.
+    pub keep_going: bool,
+    /// `-C dir`, change to `dir` before doing anything.
+    pub directory: Option<PathBuf>,
+    /// Arguments after the target.
+    pub extra_args: Vec<String>,
+    /// Run in a pseudo-terminal instead of pipes, stdout and stderr are
+    /// then one stream.
+    pub pty: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct StdCommand {
+    command: String,
+    args: Vec<String>,
+    target: String,
+    /// Runner the command comes from, which turns the run options into
+    /// arguments.
+    runner: Option<&'static dyn TaskRunner>,
+    options: RunOptions,
+    /// Directory the command runs in, the current one when not set.
+    dir: Option<PathBuf>,
+    stop_policy: StopPolicy,
+    /// Columns and rows of the pseudo-terminal the command starts in.
+    terminal_size: (u16, u16),
+}
+
+impl StdCommand {
+    pub fn new(target: String, command: String) -> Self {
+        Self {
+            command: command.clone(),
+            args: Vec::new(),
+            target: target.clone(),
+            runner: None,
+            options: RunOptions::default(),
+            dir: None,
+            stop_policy: StopPolicy::default(),
+            terminal_size: pty::DEFAULT_SIZE,
+        }
+    }
+
+    /// Arguments passed to the command ahead of the target.
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    pub fn with_runner(mut self, runner: &'static dyn TaskRunner) -> Self {
+        self.runner = Some(runner);
+        self
+    }
+
+    pub fn runner(&self) -> Option<&'static dyn TaskRunner> {
+        self.runner
+    }
+
+    /// The command's runner takes the variables, jobs, keep going and
+    /// directory options.
+    pub fn takes_options(&self) -> bool {
+        self.runner
+            .is_some_and(|runner| runner.option_args(&RunOptions::default()).is_some())
+    }
+
+    /// Program run, e.g. `make`.
+    pub fn program(&self) -> &str {
+        &self.command
+    }
+
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    pub fn in_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn options(&self) -> &RunOptions {
+        &self.options
+    }
+
+    pub fn with_stop_policy(mut self, stop_policy: StopPolicy) -> Self {
+        self.stop_policy = stop_policy;
+        self
+    }
+
+    /// Starts a command run in a pseudo-terminal `cols` by `rows`.
+    pub fn with_terminal_size(mut self, (cols, rows): (u16, u16)) -> Self {
+        self.terminal_size = (cols, rows);
+        self
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Everything passed to the command, in order.
+    fn argv(&self) -> Vec<String> {
+        let mut argv = self.args.clone();
+        if let Some(args) = self
+            .runner
+            .and_then(|runner| runner.option_args(&self.options))
+        {
+            argv.extend(args);
+        }
+        argv.push(self.target.clone());
+        argv.extend(self.options.extra_args.iter().cloned());
+        argv
+    }
+
+    /// The command as it would be typed in a shell.
+    pub fn command_line(&self) -> String {
+        [self.command.clone()]
+            .into_iter()
+            .chain(self.argv().iter().map(|arg| quote(arg)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Commands sent from the ui to a running worker.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Control {
+    Stop,
+    /// Text written to the task's stdin, lines end with `\n`.
+    Input(String),
+    /// Closes the task's stdin, or sends end of file (ctrl-d) to its
+    /// terminal.
+    Eof,
+    /// The terminal of a task run in a pseudo-terminal is now `cols` by
+    /// `rows`.
+    Resize {
+        cols: u16,
+        rows: u16,
+    },
+}
+
+/// The pipe a line of task output was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub source: Source,
+    pub text: String,
+}
+
+impl Line {
+    pub fn stdout(text: impl Into<String>) -> Self {
+        Self {
+            source: Source::Stdout,
+            text: text.into(),
+        }
+    }
+
+    pub fn stderr(text: impl Into<String>) -> Self {
+        Self {
+            source: Source::Stderr,
+            text: text.into(),
+        }
+    }
+}
+
+/// How and when a task ended.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exit {
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    /// The task was ended by a stop request rather than finishing on its own.
+    pub stopped: bool,
+    pub started: SystemTime,
+    pub ended: SystemTime,
+    pub duration: Duration,
+}
+
+impl Exit {
+    fn new(status: ExitStatus, stopped: bool, started: SystemTime, clock: Instant) -> Self {
+        Self {
+            code: status.code(),
+            signal: status.signal(),
+            stopped,
+            started,
+            ended: SystemTime::now(),
+            duration: clock.elapsed(),
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Name of the signal that ended the process, e.g. `SIGKILL`.
+    pub fn signal_name(&self) -> Option<&'static str> {
+        self.signal
+            .and_then(|signal| Signal::try_from(signal).ok())
+            .map(Signal::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Stdout {
+    Ready(mpsc::Sender<Control>),
+    Prepare {
+        output: Vec<Line>,
+    },
+    OutputUpdate {
+        output: Vec<Line>,
+    },
+    /// The unfinished last line of a pseudo-terminal so far, replaced by the
+    /// next update.
+    Partial(Line),
+    Finished(Exit),
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    Failed(Arc<std::io::Error>),
+    NoContent,
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Failed(Arc::new(error))
+    }
+}
+
+fn signal_group(pid: Option<u32>, signal: Signal) {
+    let Some(pid) = pid else {
+        return;
+    };
+    debug!("sending {signal:?} to process group {pid}");
+    if let Err(error) = killpg(Pid::from_raw(pid as i32), signal) {
+        warn!("failed to send {signal:?} to process group {pid}: {error}");
+    }
+}
+
+/// Stream of everything `f` sends, followed by the error it returns, if any.
+fn try_channel<T, E, F>(
+    size: usize,
+    f: impl FnOnce(mpsc::Sender<T>) -> F,
+) -> impl Stream<Item = Result<T, E>>
+where
+    F: Future<Output = Result<(), E>>,
+{
+    let (sender, receiver) = mpsc::channel(size);
+
+    let runner = stream::once(f(sender)).filter_map(|result| async {
+        match result {
+            Ok(()) => None,
+            Err(error) => Some(Err(error)),
+        }
+    });
+
+    stream::select(receiver.map(Ok), runner)
+}
+
+/// Splits raw output into lines, keeping the unfinished last one.
+#[derive(Debug, Default)]
+struct Lines {
+    pending: Vec<u8>,
+}
+
+impl Lines {
+    /// Lines finished by `bytes`, without their `\r\n` ending.
+    fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(bytes);
+        let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = self.pending.split_off(end + 1);
+        let done = std::mem::replace(&mut self.pending, rest);
+        done[..end]
+            .split(|&b| b == b'\n')
+            .map(|line| String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)).into())
+            .collect()
+    }
+
+    /// The unfinished line so far, leaving out a character still being
+    /// written.
+    fn partial(&self) -> Option<String> {
+        let end = match std::str::from_utf8(&self.pending) {
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            _ => self.pending.len(),
+        };
+        (end > 0).then(|| String::from_utf8_lossy(&self.pending[..end]).into())
+    }
+
+    /// The unfinished line once the output is closed.
+    fn finish(self) -> Option<String> {
+        (!self.pending.is_empty()).then(|| String::from_utf8_lossy(&self.pending).into())
+    }
+}
+
+/// What the worker reads from a task.
+enum Read {
+    Line(Line),
+    /// The unfinished last line so far, like a prompt waiting for input.
+    Partial(Line),
+}
+
+/// Lines of a pipe or pseudo-terminal as they are written, until it is
+/// closed, or until every process using a terminal has closed it.
+fn read_lines<R>(reader: R, line: fn(String) -> Line) -> BoxStream<'static, Read>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    stream::unfold(Some((reader, Lines::default())), move |state| async move {
+        let (mut reader, mut lines) = state?;
+        let mut buffer = [0; 4096];
+        match reader.read(&mut buffer).await {
+            // reading a terminal fails rather than ending once the task is gone
+            Ok(0) | Err(_) => {
+                let last = lines.finish().map(|text| Read::Line(line(text)));
+                Some((last.into_iter().collect(), None))
+            }
+            Ok(n) => {
+                let mut reads: Vec<Read> = lines
+                    .push(&buffer[..n])
+                    .into_iter()
+                    .map(|text| Read::Line(line(text)))
+                    .collect();
+                reads.extend(lines.partial().map(|text| Read::Partial(line(text))));
+                Some((reads, Some((reader, lines))))
+            }
+        }
+    })
+    .flat_map(stream::iter)
+    .boxed()
+}
+
+/// Writes `bytes` to the task's input.
+async fn write_input(input: &mut Input, bytes: &[u8]) -> std::io::Result<()> {
+    input.write_all(bytes).await?;
+    input.flush().await
+}
+
+type Input = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// Writes what is queued to the task's input until the queue is dropped,
+/// which ends the input, or a write fails.
+async fn write_queue(input: Option<Input>, mut queue: mpsc::UnboundedReceiver<Vec<u8>>) {
+    let Some(mut input) = input else {
+        return;
+    };
+    while let Some(bytes) = queue.next().await {
+        if let Err(error) = write_input(&mut input, &bytes).await {
+            warn!("failed to write to task input: {error}");
+            return;
+        }
+    }
+}
+
+/// Runs `command`, streaming its output as it is produced and finishing with
+/// [`Stdout::Finished`]. The first [`Stdout::Ready`] carries the channel used
+/// to [`Control`] the running process.
+pub fn run(command: StdCommand) -> impl Stream<Item = Result<Stdout, Error>> {
+    try_channel(1, move |mut output| async move {
+        let _ = output
+            .send(Stdout::OutputUpdate {
+                output: vec![Line::stdout("")],
+            })
+            .await;
+        debug!("initialize worker: {:?}", command.target.clone());
+        let mut cmd = Command::new(command.command.as_str());
+
+        let terminal = match command.options.pty {
+            true => Some(pty::open(command.terminal_size)?),
+            false => None,
+        };
+        if let Some((_, task_end)) = &terminal {
+            cmd.stdin(task_end.try_clone()?);
+            cmd.stdout(task_end.try_clone()?);
+            cmd.stderr(task_end.try_clone()?);
+            cmd.env("TERM", "xterm-256color");
+            // The task leads a new session on the terminal, which also
+            // makes it a process group a stop can reach.
+            // SAFETY: only calls async signal safe functions.
+            unsafe { cmd.pre_exec(pty::set_controlling_terminal) };
+        } else {
+            // Specify that we want the command's standard output piped back to us.
+            // By default, standard input/output/error will be inherited from the
+            // current process (for example, this means that standard input will
+            // come from the keyboard and standard output/error will go directly to
+            // the terminal if this process is invoked from the command line).
+            cmd.stdout(Stdio::piped());
+            cmd.stdin(Stdio::piped());
+            cmd.stderr(Stdio::piped());
+
+            // Run the task in its own process group so a stop reaches every
+            // process make spawns.
+            cmd.process_group(0);
+        }
+        // Never leave the task behind if the worker is dropped without
+        // being stopped.
+        cmd.kill_on_drop(true);
+        if let Some(dir) = &command.dir {
+            cmd.current_dir(dir);
+        }
+
+        let started = SystemTime::now();
+        let clock = Instant::now();
+        let mut child = cmd.args(command.argv()).spawn()?;
+        let pid = child.id();
+        // Only the task may keep its end of the terminal open, reading it
+        // ends when the task does.
+        drop(cmd);
+        let terminal = terminal.map(|(terminal, _task_end)| terminal);
+
+        let (sender, mut control) = mpsc::channel(8);
+        let _ = output.send(Stdout::Ready(sender)).await;
+
+        let (mut reader, stdin, terminal): (_, Option<Input>, _) = match terminal {
+            Some(terminal) => {
+                let reader = tokio::fs::File::from_std(terminal.try_clone()?.into());
+                let writer = tokio::fs::File::from_std(terminal.try_clone()?.into());
+                (
+                    read_lines(reader, Line::stdout),
+                    Some(Box::new(writer)),
+                    Some(terminal),
+                )
+            }
+            None => {
+                let stdout = child
+                    .stdout
+                    .take()
+                    .expect("child did not have a handle to stdout");
+                let stderr = child
+                    .stderr
+                    .take()
+                    .expect("child did not have a handle to stderr");
+                // Both pipes are drained together, a child blocked on a full
+                // stderr pipe would otherwise never finish writing to stdout.
+                let reader = stream::select(
+                    read_lines(stdout, Line::stdout),
+                    read_lines(stderr, Line::stderr),
+                );
+                let stdin = child.stdin.take().map(|stdin| Box::new(stdin) as Input);
+                (reader.boxed(), stdin, None)
+            }
+        };
+        // Input is written alongside reading the output and handling stops, a
+        // task that doesn't read it would otherwise block the worker.
+        let (queue, writes) = mpsc::unbounded();
+        let mut input = Some(queue);
+        let writer = write_queue(stdin, writes);
+        tokio::pin!(writer);
+        let mut written = false;
+        let mut cache: Vec<Line> = Vec::new();
+        let mut partial: Option<Line> = None;
+        // the ui drops the unfinished line with every update, it is sent again
+        let mut partial_changed = false;
+        let mut eof = false;
+        let mut stopping = false;
+        let mut kill_at: Option<Instant> = None;
+        let interval = time::interval(time::Duration::from_millis(80));
+        tokio::pin!(interval);
+        let status = loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let updated = !cache.is_empty();
+                    if updated {
+                        let _ = output.send(Stdout::OutputUpdate { output: cache.clone() }).await;
+                        cache.clear();
+                    }
+                    match &partial {
+                        Some(line) if updated || partial_changed => {
+                            let _ = output.send(Stdout::Partial(line.clone())).await;
+                        }
+                        _ => {}
+                    }
+                    partial_changed = false;
+                }
+                read = reader.next(), if !eof => match read {
+                    Some(Read::Line(line)) => {
+                        if partial.as_ref().is_some_and(|p| p.source == line.source) {
+                            partial = None;
+                        }
+                        cache.push(line);
+                    }
+                    Some(Read::Partial(line)) => {
+                        partial = Some(line);
+                        partial_changed = true;
+                    }
+                    None => eof = true,
+                },
+                Some(request) = control.next() => match request {
+                    Control::Stop if !stopping => {
+                        stopping = true;
+                        signal_group(pid, command.stop_policy.signal.signal());
+                        kill_at = Some(Instant::now() + command.stop_policy.grace_period);
+                    }
+                    Control::Stop => {}
+                    Control::Input(text) => {
+                        if input.as_ref().is_some_and(|queue| queue.unbounded_send(text.into_bytes()).is_err()) {
+                            warn!("task input is closed");
+                            input = None;
+                        }
+                    }
+                    // a terminal reads ctrl-d as the end of its input
+                    Control::Eof if terminal.is_some() => {
+                        if let Some(queue) = &input {
+                            let _ = queue.unbounded_send(b"\x04".to_vec());
+                        }
+                    }
+                    // the writer ends the input once what is queued is written
+                    Control::Eof => input = None,
+                    Control::Resize { cols, rows } => {
+                        if let Some(terminal) = &terminal {
+                            if let Err(error) = pty::resize(terminal, (cols, rows)) {
+                                warn!("failed to resize terminal: {error}");
+                            }
+                        }
+                    }
+                },
+                _ = &mut writer, if !written => written = true,
+                _ = time::sleep_until(kill_at.unwrap_or_else(Instant::now)), if kill_at.is_some() => {
+                    kill_at = None;
+                    signal_group(pid, Signal::SIGKILL);
+                }
+                status = child.wait(), if eof => break status?,
+            }
+        };
+        if !cache.is_empty() {
+            let _ = output
+                .send(Stdout::OutputUpdate {
+                    output: cache.clone(),
+                })
+                .await;
+            cache.clear();
+        }
+        let exit = Exit::new(status, stopping, started, clock);
+        debug!("task {:?} exited: {exit:?}", command.target);
+        let _ = output.send(Stdout::Finished(exit)).await;
+        debug!("leaving worker");
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_runners::npm::PackageManager;
+    use crate::task_runners::{just, makefile, npm};
+
+    /// Runs `command` and stops it once it prints `ready`, or as soon as it
+    /// has been spawned when `ready` is `None`.
+    async fn stop_worker(command: StdCommand, ready: Option<&str>) -> Exit {
+        let mut worker = Box::pin(run(command));
+        let mut control = None;
+        let mut finished = None;
+        while let Some(update) = worker.next().await {
+            match update.unwrap() {
+                Stdout::Ready(mut c) if ready.is_none() => c.send(Control::Stop).await.unwrap(),
+                Stdout::Ready(c) => control = Some(c),
+                Stdout::OutputUpdate { output }
+                    if output.iter().any(|l| Some(l.text.as_str()) == ready) =>
+                {
+                    control.as_mut().unwrap().send(Control::Stop).await.unwrap()
+                }
+                Stdout::Finished(exit) => finished = Some(exit),
+                _ => {}
+            }
+        }
+        let exit = finished.unwrap();
+        assert!(exit.stopped);
+        exit
+    }
+
+    #[tokio::test]
+    async fn test_stop_interrupts_process_group() {
+        let command = StdCommand::new("30".to_string(), "sleep".to_string());
+        let exit = stop_worker(command, None).await;
+        assert_eq!(exit.signal, Some(Signal::SIGINT as i32));
+    }
+
+    #[tokio::test]
+    async fn test_stop_terminates_process_group() {
+        let command =
+            StdCommand::new("30".to_string(), "sleep".to_string()).with_stop_policy(StopPolicy {
+                signal: StopSignal::Terminate,
+                ..StopPolicy::default()
+            });
+        let exit = stop_worker(command, None).await;
+        assert_eq!(exit.signal, Some(Signal::SIGTERM as i32));
+    }
+
+    #[tokio::test]
+    async fn test_stop_escalates_to_kill() {
+        let command = StdCommand::new(
+            "tests/test_files/ignore_interrupt.sh".to_string(),
+            "sh".to_string(),
+        )
+        .with_stop_policy(StopPolicy {
+            signal: StopSignal::Interrupt,
+            grace_period: Duration::from_millis(200),
+        });
+        let exit = stop_worker(command, Some("ready")).await;
+        assert_eq!(exit.signal, Some(Signal::SIGKILL as i32));
+    }
+
+    #[tokio::test]
+    async fn test_output_is_tagged_with_source() {
+        let command = StdCommand::new(
+            "tests/test_files/stdout_stderr.sh".to_string(),
+            "sh".to_string(),
+        );
+        let mut worker = Box::pin(run(command));
+        let mut lines = Vec::new();
+        while let Some(update) = worker.next().await {
+            if let Stdout::OutputUpdate { output } = update.unwrap() {
+                lines.extend(output.into_iter().filter(|l| !l.text.is_empty()));
+            }
+        }
+        lines.sort_by(|a, b| a.text.cmp(&b.text));
+        assert_eq!(lines, vec![Line::stderr("error"), Line::stdout("output")]);
+    }
+
+    #[tokio::test]
+    async fn test_exit_code_and_duration() {
+        let command = StdCommand::new(
+            "tests/test_files/exit_code.sh".to_string(),
+            "sh".to_string(),
+        );
+        let mut worker = Box::pin(run(command));
+        let mut exit = None;
+        while let Some(update) = worker.next().await {
+            if let Stdout::Finished(e) = update.unwrap() {
+                exit = Some(e);
+            }
+        }
+        let exit = exit.unwrap();
+        assert_eq!(exit.code, Some(3));
+        assert_eq!(exit.signal, None);
+        assert!(!exit.stopped);
+        assert!(!exit.success());
+        assert!(exit.ended >= exit.started);
+        assert!(exit.duration >= Duration::from_millis(100));
+    }
+
+    fn make_options() -> RunOptions {
+        RunOptions {
+            variables: vec!["CFLAGS=-O2 -g".to_string(), "V=1".to_string()],
+            jobs: Some(4),
+            keep_going: true,
+            directory: Some(PathBuf::from("src")),
+            extra_args: vec!["test".to_string()],
+            pty: true,
+        }
+    }
+
+    #[test]
+    fn test_command_line_with_options() {
+        let command = makefile::new("build".to_string());
+        assert_eq!(command.command_line(), "make build");
+        assert!(command.takes_options());
+        let command = command.with_options(make_options());
+        assert_eq!(
+            command.command_line(),
+            "make -C src -j4 -k 'CFLAGS=-O2 -g' V=1 build test"
+        );
+
+        // other tools only get the extra arguments
+        let command = npm::new(PackageManager::Npm, "lint".to_string());
+        assert!(!command.takes_options());
+        let command = command.with_options(make_options());
+        assert_eq!(command.command_line(), "npm run lint test");
+        let command = just::new("lint".to_string()).with_options(make_options());
+        assert_eq!(command.command_line(), "just lint test");
+    }
+
+    #[tokio::test]
+    async fn test_run_with_options() {
+        let command = StdCommand::new("x".to_string(), "echo".to_string())
+            .with_runner(&npm::Npm)
+            .with_options(RunOptions {
+                extra_args: vec!["y z".to_string()],
+                pty: false,
+                ..make_options()
+            });
+        let mut worker = Box::pin(run(command));
+        let mut lines = Vec::new();
+        while let Some(update) = worker.next().await {
+            if let Stdout::OutputUpdate { output } = update.unwrap() {
+                lines.extend(output.into_iter().filter(|l| !l.text.is_empty()));
+            }
+        }
+        assert_eq!(lines, vec![Line::stdout("x y z")]);
+    }
+
+    #[tokio::test]
+    async fn test_run_in_dir() {
+        let command = StdCommand::new("single_line.txt".to_string(), "cat".to_string())
+            .in_dir("tests/test_files");
+        let mut worker = Box::pin(run(command));
+        let mut lines = Vec::new();
+        while let Some(update) = worker.next().await {
+            if let Stdout::OutputUpdate { output } = update.unwrap() {
+                lines.extend(output.into_iter().filter(|l| !l.text.is_empty()));
+            }
+        }
+        assert_eq!(lines, vec![Line::stdout("Hello World")]);
+    }
+
+    #[tokio::test]
+    async fn test_run_in_pty() {
+        let script = "[ -t 1 ] && echo tty; stty size; printf '50%%'; sleep 0.3; printf '\\r100%%'";
+        let command = StdCommand::new(script.to_string(), "sh".to_string())
+            .with_args(vec!["-c".to_string()])
+            .with_options(RunOptions {
+                pty: true,
+                ..RunOptions::default()
+            })
+            .with_terminal_size((100, 30));
+        let mut worker = Box::pin(run(command));
+        let mut lines = Vec::new();
+        let mut partials = Vec::new();
+        let mut exit = None;
+        while let Some(update) = worker.next().await {
+            match update.unwrap() {
+                Stdout::OutputUpdate { output } => {
+                    lines.extend(output.into_iter().filter(|l| !l.text.is_empty()))
+                }
+                Stdout::Partial(line) => partials.push(line.text),
+                Stdout::Finished(e) => exit = Some(e),
+                _ => {}
+            }
+        }
+        assert!(exit.unwrap().success());
+        // the unfinished line is streamed, and kept once the terminal closes
+        assert_eq!(partials, vec!["50%"]);
+        assert_eq!(
+            lines,
+            vec![
+                Line::stdout("tty"),
+                Line::stdout("30 100"),
+                Line::stdout("50%\r100%")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut lines = Lines::default();
+        assert!(lines.push(b" 10%\r 50%").is_empty());
+        assert_eq!(lines.partial().as_deref(), Some(" 10%\r 50%"));
+        assert_eq!(
+            lines.push(b"\r100%\r\nok\r\n\xc3"),
+            vec![" 10%\r 50%\r100%", "ok"]
+        );
+        // half of `é` is not shown until the rest of it is read
+        assert_eq!(lines.partial(), None);
+        assert!(lines.push(b"\xa9t").is_empty());
+        assert_eq!(lines.partial().as_deref(), Some("ét"));
+        assert_eq!(lines.finish().as_deref(), Some("ét"));
+    }
+
+    #[tokio::test]
+    async fn test_input() {
+        let script = "printf 'name? '; read name; echo \"hello $name\"; cat";
+        let command =
+            StdCommand::new(script.to_string(), "sh".to_string()).with_args(vec!["-c".to_string()]);
+        let mut worker = Box::pin(run(command));
+        let mut control = None;
+        let mut lines = Vec::new();
+        let mut exit = None;
+        while let Some(update) = worker.next().await {
+            match update.unwrap() {
+                Stdout::Ready(c) => control = Some(c),
+                // the prompt is shown before its line is finished
+                Stdout::Partial(line) if line.text == "name? " => {
+                    let control = control.as_mut().unwrap();
+                    control.send(Control::Input("bob\n".into())).await.unwrap();
+                    control.send(Control::Input("x\n".into())).await.unwrap();
+                    control.send(Control::Eof).await.unwrap();
+                }
+                Stdout::OutputUpdate { output } => {
+                    lines.extend(output.into_iter().filter(|l| !l.text.is_empty()))
+                }
+                Stdout::Finished(e) => exit = Some(e),
+                _ => {}
+            }
+        }
+        assert!(exit.unwrap().success());
+        assert_eq!(
+            lines,
+            vec![Line::stdout("name? hello bob"), Line::stdout("x")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_input_not_read() {
+        // more input than the pipe holds, the task never reads it
+        let script = "echo ready; sleep 30";
+        let command =
+            StdCommand::new(script.to_string(), "sh".to_string()).with_args(vec!["-c".to_string()]);
+        let mut worker = Box::pin(run(command));
+        let mut control = None;
+        let mut exit = None;
+        let stopped = time::timeout(Duration::from_secs(10), async {
+            while let Some(update) = worker.next().await {
+                match update.unwrap() {
+                    Stdout::Ready(c) => control = Some(c),
+                    Stdout::OutputUpdate { output } if output.contains(&Line::stdout("ready")) => {
+                        let control = control.as_mut().unwrap();
+                        let input = "x".repeat(1 << 20);
+                        control.send(Control::Input(input)).await.unwrap();
+                        control.send(Control::Stop).await.unwrap();
+                    }
+                    Stdout::Finished(e) => exit = Some(e),
+                    _ => {}
+                }
+            }
+        })
+        .await;
+        assert!(stopped.is_ok(), "worker blocked on the task's input");
+        assert!(exit.unwrap().stopped);
+    }
+}
@@ -0,0 +1,3 @@
+trap '' INT
+echo ready
+sleep 30
@@ -0,0 +1,2 @@
+build:
+	echo build
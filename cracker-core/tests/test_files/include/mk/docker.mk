@@ -0,0 +1,4 @@
+DOCKER = docker
+
+build-$(DOCKER): build
+	echo docker
@@ -0,0 +1,2 @@
+include mk/loop.mk
+loop:
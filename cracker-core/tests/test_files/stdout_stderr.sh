@@ -0,0 +1,2 @@
+echo output
+echo error >&2
@@ -0,0 +1,417 @@
+use std::time::Instant;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use futures::channel::mpsc;
+
+use cracker_core::task_runners::{Group, Target, TargetFilter};
+use cracker_core::worker::{self, Control, Exit, Line, RunOptions, StdCommand, StopPolicy};
+
+/// Lines moved by a page up / page down in the output pane.
+const PAGE: usize = 10;
+/// Output lines kept per task.
+const MAX_OUTPUT: usize = 1_000_000;
+/// Runs kept in the history.
+const MAX_TASKS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    Running,
+    Stopping,
+    Finished,
+    Errored,
+}
+
+#[derive(Debug)]
+pub struct Task {
+    pub id: usize,
+    pub run: usize,
+    pub command: StdCommand,
+    pub state: State,
+    pub started: Instant,
+    pub exit: Option<Exit>,
+    pub output: Vec<Line>,
+    control: Option<mpsc::Sender<Control>>,
+}
+
+impl Task {
+    pub fn running(&self) -> bool {
+        matches!(self.state, State::Running | State::Stopping)
+    }
+
+    fn stop(&mut self) {
+        if self.state != State::Running {
+            return;
+        }
+        self.state = State::Stopping;
+        self.output.push(Line::stdout("stopping..."));
+        // Without a control channel yet the stop is sent once the worker
+        // reports it is ready.
+        if let Some(control) = self.control.as_mut() {
+            let _ = control.try_send(Control::Stop);
+        }
+    }
+
+    fn resize(&mut self, (cols, rows): (u16, u16)) {
+        if let Some(control) = self.control.as_mut().filter(|_| self.command.options().pty) {
+            let _ = control.try_send(Control::Resize { cols, rows });
+        }
+    }
+
+    fn update(&mut self, update: Result<worker::Stdout, worker::Error>) {
+        match update {
+            Ok(worker::Stdout::Ready(mut control)) => {
+                if self.state == State::Stopping {
+                    let _ = control.try_send(Control::Stop);
+                }
+                self.control = Some(control);
+            }
+            Ok(worker::Stdout::Prepare { output })
+            | Ok(worker::Stdout::OutputUpdate { output }) => {
+                self.output.extend(output);
+                if self.output.len() > MAX_OUTPUT {
+                    let r = self.output.len() - MAX_OUTPUT;
+                    self.output.drain(..r);
+                }
+            }
+            // The unfinished line is shown once it is finished.
+            Ok(worker::Stdout::Partial(_)) => {}
+            Ok(worker::Stdout::Finished(exit)) => {
+                self.state = State::Finished;
+                self.control = None;
+                self.exit = Some(exit);
+            }
+            Err(error) => {
+                self.state = State::Errored;
+                self.control = None;
+                self.output.push(Line::stderr(format!("{error:?}")));
+            }
+        }
+    }
+}
+
+/// What the event loop has to do after a key press.
+#[derive(Debug, Clone)]
+pub enum Action {
+    Run(usize, Box<StdCommand>),
+    Reload,
+}
+
+#[derive(Debug)]
+pub struct App {
+    pub groups: Vec<Group>,
+    pub selected: usize,
+    pub tasks: Vec<Task>,
+    /// Output lines between the bottom of the output pane and the end of
+    /// the output.
+    pub scroll: usize,
+    pub follow: bool,
+    pub quitting: bool,
+    pub filter: TargetFilter,
+    /// Run tasks in a pseudo-terminal.
+    pub pty: bool,
+    /// Columns and rows of the output pane.
+    terminal_size: Option<(u16, u16)>,
+    stop_policy: StopPolicy,
+    runs: usize,
+}
+
+impl App {
+    pub fn new(stop_policy: StopPolicy) -> Self {
+        Self {
+            groups: Vec::new(),
+            selected: 0,
+            tasks: Vec::new(),
+            scroll: 0,
+            follow: true,
+            quitting: false,
+            filter: TargetFilter::default(),
+            pty: false,
+            terminal_size: None,
+            stop_policy,
+            runs: 0,
+        }
+    }
+
+    pub fn set_groups(&mut self, groups: Vec<Group>) {
+        self.groups = groups;
+        self.select_visible();
+    }
+
+    pub fn targets(&self) -> impl Iterator<Item = &Target> {
+        self.groups.iter().flat_map(|group| group.targets.iter())
+    }
+
+    /// Ids of the targets the filter shows.
+    pub fn visible(&self) -> Vec<usize> {
+        self.targets()
+            .enumerate()
+            .filter(|(_, target)| self.filter.shows(target))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    fn select_visible(&mut self) {
+        let visible = self.visible();
+        if !visible.contains(&self.selected) {
+            self.selected = visible.first().copied().unwrap_or(0);
+        }
+    }
+
+    /// The latest run of target `id`.
+    pub fn last_task(&self, id: usize) -> Option<&Task> {
+        self.tasks.iter().rev().find(|task| task.id == id)
+    }
+
+    /// Number of rows in the output pane: a header per task and its output.
+    pub fn output_len(&self) -> usize {
+        self.tasks.iter().map(|task| task.output.len() + 1).sum()
+    }
+
+    /// Quitting waits for every running task to be stopped first.
+    pub fn done(&self) -> bool {
+        self.quitting && !self.tasks.iter().any(Task::running)
+    }
+
+    pub fn key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit(),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => self.quit(),
+            KeyCode::Char('j') | KeyCode::Down => self.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => self.select_previous(),
+            KeyCode::Enter => return self.start(),
+            KeyCode::Char('s') => self.stop(),
+            KeyCode::Char('r') => return Some(Action::Reload),
+            KeyCode::Char('f') => self.toggle_follow(),
+            KeyCode::Char('p') => self.filter.phony_only = !self.filter.phony_only,
+            KeyCode::Char('.') => self.filter.hide_hidden = !self.filter.hide_hidden,
+            KeyCode::Char('o') => self.filter.hide_object_files = !self.filter.hide_object_files,
+            KeyCode::PageUp => self.scroll_up(PAGE),
+            KeyCode::PageDown => self.scroll_down(PAGE),
+            KeyCode::Home => self.scroll_up(self.output_len()),
+            KeyCode::End => self.scroll_down(self.output_len()),
+            _ => {}
+        }
+        self.select_visible();
+        None
+    }
+
+    pub fn select_next(&mut self) {
+        let visible = self.visible();
+        if let Some(id) = visible.iter().find(|id| **id > self.selected) {
+            self.selected = *id;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        let visible = self.visible();
+        if let Some(id) = visible.iter().rev().find(|id| **id < self.selected) {
+            self.selected = *id;
+        }
+    }
+
+    /// Starts the selected target, alongside its earlier runs that are still
+    /// going.
+    pub fn start(&mut self) -> Option<Action> {
+        let id = self.selected;
+        let target = self.targets().nth(id).filter(|t| self.filter.shows(t))?;
+        let command = target.command.clone();
+        let mut command = command
+            .with_stop_policy(self.stop_policy)
+            .with_options(RunOptions {
+                pty: self.pty,
+                ..RunOptions::default()
+            });
+        if let Some(size) = self.terminal_size {
+            command = command.with_terminal_size(size);
+        }
+        self.runs += 1;
+        self.tasks.push(Task {
+            id,
+            run: self.runs,
+            command: command.clone(),
+            state: State::Running,
+            started: Instant::now(),
+            exit: None,
+            output: Vec::new(),
+            control: None,
+        });
+        // the oldest finished runs make room, running ones are kept
+        while self.tasks.len() > MAX_TASKS {
+            let Some(oldest) = self.tasks.iter().position(|task| !task.running()) else {
+                break;
+            };
+            self.tasks.remove(oldest);
+        }
+        if self.follow {
+            self.scroll = 0;
+        }
+        Some(Action::Run(self.runs, Box::new(command)))
+    }
+
+    /// Stops every run of the selected target.
+    pub fn stop(&mut self) {
+        let id = self.selected;
+        self.tasks
+            .iter_mut()
+            .filter(|task| task.id == id)
+            .for_each(Task::stop);
+    }
+
+    /// Tells tasks in a terminal the output pane is now `size` columns and
+    /// rows.
+    pub fn resize(&mut self, size: (u16, u16)) {
+        if self.terminal_size != Some(size) {
+            self.terminal_size = Some(size);
+            self.tasks.iter_mut().for_each(|task| task.resize(size));
+        }
+    }
+
+    pub fn quit(&mut self) {
+        self.quitting = true;
+        self.tasks.iter_mut().for_each(Task::stop);
+    }
+
+    pub fn update(&mut self, run: usize, update: Result<worker::Stdout, worker::Error>) {
+        let before = self.output_len();
+        if let Some(task) = self.tasks.iter_mut().rev().find(|task| task.run == run) {
+            task.update(update);
+        }
+        // Keep the lines on screen where they are unless following.
+        if !self.follow {
+            self.scroll += self.output_len().saturating_sub(before);
+        }
+    }
+
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        if self.follow {
+            self.scroll = 0;
+        }
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.output_len());
+        self.follow = false;
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+        if self.scroll == 0 {
+            self.follow = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn app() -> App {
+        let mut app = App::new(StopPolicy::default());
+        app.set_groups(vec![Group {
+            runner: "make",
+            file: PathBuf::from("Makefile"),
+            dir: PathBuf::from("."),
+            targets: ["build", "test"]
+                .into_iter()
+                .map(|name| {
+                    let command = StdCommand::new(name.to_string(), "make".to_string());
+                    Target::new(command, "Makefile")
+                })
+                .collect(),
+        }]);
+        app
+    }
+
+    #[test]
+    fn test_selection_stays_in_bounds() {
+        let mut app = app();
+        app.select_previous();
+        assert_eq!(app.selected, 0);
+        app.select_next();
+        app.select_next();
+        assert_eq!(app.selected, 1);
+    }
+
+    #[test]
+    fn test_start_runs_selected_target() {
+        let mut app = app();
+        app.select_next();
+        let Some(Action::Run(run, command)) = app.start() else {
+            panic!("no run started");
+        };
+        assert_eq!(run, 1);
+        assert_eq!(command.command_line(), "make test");
+        assert!(app.last_task(1).unwrap().running());
+        assert!(app.last_task(0).is_none());
+    }
+
+    #[test]
+    fn test_runs_of_a_target_go_side_by_side() {
+        let mut app = app();
+        app.start();
+        app.start();
+        assert!(app.tasks.iter().all(Task::running));
+        app.stop();
+        assert!(app.tasks.iter().all(|task| task.state == State::Stopping));
+    }
+
+    #[test]
+    fn test_output_keeps_position_when_not_following() {
+        let mut app = app();
+        app.start();
+        let lines = vec![Line::stdout("a"), Line::stdout("b")];
+        app.update(
+            1,
+            Ok(worker::Stdout::OutputUpdate {
+                output: lines.clone(),
+            }),
+        );
+        assert_eq!(app.scroll, 0);
+
+        app.scroll_up(1);
+        assert!(!app.follow);
+        app.update(1, Ok(worker::Stdout::OutputUpdate { output: lines }));
+        assert_eq!(app.scroll, 3);
+
+        app.scroll_down(app.output_len());
+        assert!(app.follow);
+        assert_eq!(app.scroll, 0);
+    }
+
+    #[test]
+    fn test_quit_waits_for_running_tasks() {
+        let mut app = app();
+        app.start();
+        let (control, mut receiver) = mpsc::channel(1);
+        app.update(1, Ok(worker::Stdout::Ready(control)));
+        app.quit();
+        assert_eq!(receiver.try_recv().unwrap(), Control::Stop);
+        assert!(!app.done());
+        app.update(1, Err(worker::Error::NoContent));
+        assert!(app.done());
+    }
+
+    #[test]
+    fn test_selection_skips_filtered_targets() {
+        let mut app = app();
+        let mut hidden = Target::new(
+            StdCommand::new("_hidden".to_string(), "make".to_string()),
+            "Makefile",
+        );
+        hidden.phony = false;
+        app.groups[0].targets.insert(1, hidden);
+        app.select_next();
+        assert_eq!(app.selected, 2);
+        app.select_previous();
+        assert_eq!(app.selected, 0);
+
+        app.filter.hide_hidden = false;
+        app.select_next();
+        assert_eq!(app.selected, 1);
+        app.key(KeyEvent::from(KeyCode::Char('p')));
+        assert!(app.filter.phony_only);
+        assert_eq!(app.selected, 0);
+    }
+}
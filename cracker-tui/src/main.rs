@@ -0,0 +1,90 @@
+extern crate pretty_env_logger;
+#[macro_use]
+extern crate log;
+
+mod app;
+mod ui;
+
+use std::env;
+use std::io;
+use std::path::PathBuf;
+
+use crossterm::event::{Event, EventStream, KeyEventKind};
+use futures::stream::{BoxStream, SelectAll};
+use futures::StreamExt;
+use ratatui::DefaultTerminal;
+
+use app::{Action, App};
+use cracker_core::args::{parse_args_or_exit, Args, Program};
+use cracker_core::task_runners;
+use cracker_core::worker;
+
+const PROGRAM: Program = Program {
+    name: env!("CARGO_PKG_NAME"),
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+};
+
+type Updates = SelectAll<BoxStream<'static, (usize, Result<worker::Stdout, worker::Error>)>>;
+
+#[tokio::main]
+async fn main() -> io::Result<()> {
+    pretty_env_logger::init();
+    debug!("start ck-tui");
+
+    let args = parse_args_or_exit(&PROGRAM, &env::args().collect::<Vec<_>>());
+    let mut app = App::new(args.stop_policy);
+    app.pty = args.pty;
+    load(&mut app, &args).await;
+
+    let terminal = ratatui::init();
+    let result = run(terminal, app, &args).await;
+    ratatui::restore();
+    result
+}
+
+async fn load(app: &mut App, args: &Args) {
+    let file = args.filename.as_ref().map(PathBuf::from);
+    match task_runners::groups(file, args.discovery, args.directory.clone()).await {
+        Ok(groups) => app.set_groups(groups),
+        Err(error) => {
+            warn!("failed to load targets: {error:?}");
+            app.set_groups(Vec::new());
+        }
+    }
+}
+
+async fn run(mut terminal: DefaultTerminal, mut app: App, args: &Args) -> io::Result<()> {
+    let mut events = EventStream::new();
+    let mut updates: Updates = SelectAll::new();
+    // Redraw now and then so running times keep counting without output.
+    let mut tick = tokio::time::interval(tokio::time::Duration::from_secs(1));
+
+    while !app.done() {
+        let mut size = None;
+        terminal.draw(|frame| size = Some(ui::draw(frame, &app)))?;
+        if let Some(size) = size {
+            app.resize(size);
+        }
+
+        tokio::select! {
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    match app.key(key) {
+                        Some(Action::Run(run, command)) => {
+                            updates.push(worker::run(*command).map(move |update| (run, update)).boxed());
+                        }
+                        Some(Action::Reload) => load(&mut app, args).await,
+                        None => {}
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(error)) => return Err(error),
+                None => break,
+            },
+            Some((run, update)) = updates.next(), if !updates.is_empty() => app.update(run, update),
+            _ = tick.tick() => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use cracker_core::ansi;
+use cracker_core::utils::format_duration;
+use cracker_core::worker::{self, Source};
+
+use crate::app::{App, State, Task};
+
+fn color(color: ansi::Color) -> Color {
+    match color {
+        ansi::Color::Indexed(i) => Color::Indexed(i),
+        ansi::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
+
+/// A line of output with its ANSI styles, stderr is red unless the program
+/// set a colour.
+fn styled(line: &worker::Line) -> Line<'static> {
+    let spans: Vec<Span> = ansi::parse(&line.text)
+        .into_iter()
+        .map(|ansi::Span { text, style }| {
+            let modifiers = [
+                (style.bold, Modifier::BOLD),
+                (style.dim, Modifier::DIM),
+                (style.italic, Modifier::ITALIC),
+                (style.underline, Modifier::UNDERLINED),
+                (style.inverse, Modifier::REVERSED),
+            ]
+            .into_iter()
+            .filter(|(set, _)| *set)
+            .fold(Modifier::empty(), |modifiers, (_, modifier)| {
+                modifiers | modifier
+            });
+            let mut span_style = Style::new().add_modifier(modifiers);
+            span_style.fg = style.fg.map(color);
+            span_style.bg = style.bg.map(color);
+            Span::styled(text, span_style)
+        })
+        .collect();
+    match line.source {
+        Source::Stdout => Line::from(spans),
+        Source::Stderr => Line::from(spans).red(),
+    }
+}
+
+const HELP: &str =
+    " j/k select  enter run  s stop  pgup/pgdn scroll  home/end top/bottom  f follow  p phony  . hidden  o objects  r reload  q quit";
+
+/// Draws the app, returning the columns and rows inside the output pane.
+pub fn draw(frame: &mut Frame, app: &App) -> (u16, u16) {
+    let [body, help] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+    let targets_width = app
+        .targets()
+        .map(|target| target.name().len() as u16 + 24)
+        .max()
+        .unwrap_or(20)
+        .clamp(20, body.width / 2);
+    let [targets, output] =
+        Layout::horizontal([Constraint::Length(targets_width), Constraint::Fill(1)]).areas(body);
+
+    draw_targets(frame, targets, app);
+    draw_output(frame, output, app);
+    frame.render_widget(Paragraph::new(HELP).dark_gray(), help);
+    (
+        output.width.saturating_sub(2),
+        output.height.saturating_sub(2),
+    )
+}
+
+/// Pass/fail status of a run and how long it took, or has been running.
+fn badge(task: &Task) -> Span<'static> {
+    match (task.state, &task.exit) {
+        (State::Running | State::Stopping, _) => Span::raw(format!(
+            "RUNNING {}",
+            format_duration(task.started.elapsed())
+        ))
+        .blue(),
+        (State::Errored, _) => Span::raw("ERROR").red(),
+        (_, Some(exit)) => {
+            let duration = format_duration(exit.duration);
+            if exit.stopped {
+                Span::raw(format!("STOPPED {duration}")).dark_gray()
+            } else if exit.success() {
+                Span::raw(format!("PASS {duration}")).green()
+            } else if let Some(code) = exit.code {
+                Span::raw(format!("FAIL exit {code} {duration}")).red()
+            } else {
+                let signal = exit.signal_name().unwrap_or("signal");
+                Span::raw(format!("FAIL {signal} {duration}")).red()
+            }
+        }
+        _ => Span::raw(""),
+    }
+}
+
+fn draw_targets(frame: &mut Frame, area: Rect, app: &App) {
+    let mut items = Vec::new();
+    let mut selected = None;
+    let mut id = 0;
+    for group in &app.groups {
+        if group.targets.iter().any(|t| app.filter.shows(t)) {
+            items.push(ListItem::new(Line::from(vec![
+                Span::raw(format!("{} ({}) ", group.runner, group.file.display())).bold(),
+                Span::raw(format!("in {}", group.dir.display())).dark_gray(),
+            ])));
+        }
+        for target in &group.targets {
+            if !app.filter.shows(target) {
+                id += 1;
+                continue;
+            }
+            if id == app.selected {
+                selected = Some(items.len());
+            }
+            let mut line = vec![Span::raw(format!(" {} ", target.name()))];
+            if let Some(source) = group.source(target) {
+                line.push(Span::raw(format!("{source} ")).dark_gray());
+            }
+            if let Some(description) = &target.description {
+                line.push(Span::raw(format!("{description} ")).dark_gray());
+            }
+            if let Some(task) = app.last_task(id) {
+                line.push(badge(task));
+            }
+            items.push(ListItem::new(Line::from(line)));
+            id += 1;
+        }
+    }
+
+    let list = List::new(items)
+        .block(Block::bordered().title(" targets "))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(selected);
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_output(frame: &mut Frame, area: Rect, app: &App) {
+    let height = area.height.saturating_sub(2) as usize;
+    let end = app.output_len().saturating_sub(app.scroll);
+    let start = end.saturating_sub(height);
+
+    // Only the rows on screen are built, tasks above them are skipped whole.
+    let mut lines: Vec<Line> = Vec::with_capacity(end - start);
+    let mut row = 0;
+    for task in &app.tasks {
+        let rows = task.output.len() + 1;
+        if row + rows <= start {
+            row += rows;
+            continue;
+        }
+        if row >= end {
+            break;
+        }
+        if row >= start {
+            let mut header = vec![
+                Span::raw(task.command.command_line()).bold(),
+                Span::raw(" "),
+                badge(task),
+            ];
+            if let Some(dir) = task.command.dir() {
+                header.push(Span::raw(format!(" in {}", dir.display())).dark_gray());
+            }
+            lines.push(Line::from(header));
+        }
+        let first = start.saturating_sub(row + 1);
+        let last = (end - row - 1).min(task.output.len());
+        lines.extend(task.output[first..last].iter().map(styled));
+        row += rows;
+    }
+
+    let title = if app.follow {
+        " output (following) "
+    } else {
+        " output "
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title)),
+        area,
+    );
+}
@@ -0,0 +1,331 @@
+use iced::keyboard::{key::Named, Key, Modifiers};
+use std::fmt;
+use std::str::FromStr;
+
+use cracker_core::utils::Error;
+
+/// What a key binding does in normal mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    SelectNext,
+    SelectPrevious,
+    Run,
+    RunWithOptions,
+    Stop,
+    NextTab,
+    PreviousTab,
+    ScrollToBeginning,
+    ScrollToEnd,
+    Search,
+    History,
+    /// Focus the input of the task in the active tab.
+    Input,
+    /// End the input of the task in the active tab.
+    SendEof,
+    /// Focus the search of the output in the active tab.
+    SearchOutput,
+    SearchNext,
+    SearchPrevious,
+}
+
+impl Command {
+    pub const ALL: [Command; 16] = [
+        Command::SelectNext,
+        Command::SelectPrevious,
+        Command::Run,
+        Command::RunWithOptions,
+        Command::Stop,
+        Command::NextTab,
+        Command::PreviousTab,
+        Command::ScrollToBeginning,
+        Command::ScrollToEnd,
+        Command::Search,
+        Command::History,
+        Command::Input,
+        Command::SendEof,
+        Command::SearchOutput,
+        Command::SearchNext,
+        Command::SearchPrevious,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Command::SelectNext => "select-next",
+            Command::SelectPrevious => "select-previous",
+            Command::Run => "run",
+            Command::RunWithOptions => "run-with-options",
+            Command::Stop => "stop",
+            Command::NextTab => "next-tab",
+            Command::PreviousTab => "previous-tab",
+            Command::ScrollToBeginning => "scroll-to-beginning",
+            Command::ScrollToEnd => "scroll-to-end",
+            Command::Search => "search",
+            Command::History => "history",
+            Command::Input => "input",
+            Command::SendEof => "send-eof",
+            Command::SearchOutput => "search-output",
+            Command::SearchNext => "search-next",
+            Command::SearchPrevious => "search-previous",
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Command {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Command::ALL
+            .into_iter()
+            .find(|command| command.name() == s)
+            .ok_or_else(|| Error::Parse(format!("unknown command: {s}")))
+    }
+}
+
+/// One key press, e.g. `g`, `G`, `enter` or `ctrl-c`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Chord {
+    key: Key,
+    shift: bool,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl Chord {
+    /// Letters are kept lower case with shift set, other characters ignore
+    /// shift as it is already part of the character.
+    fn new(key: Key, modifiers: Modifiers) -> Self {
+        let (key, shift) = match key {
+            Key::Character(c) if c.chars().all(char::is_alphabetic) => {
+                let lower = c.to_lowercase();
+                let shift = modifiers.shift() || lower != c.as_str();
+                (Key::Character(lower.into()), shift)
+            }
+            Key::Character(c) => (Key::Character(c), false),
+            key => (key, modifiers.shift()),
+        };
+        Self {
+            key,
+            shift,
+            ctrl: modifiers.control(),
+            alt: modifiers.alt(),
+        }
+    }
+}
+
+fn named(name: &str) -> Option<Named> {
+    Some(match name {
+        "enter" => Named::Enter,
+        "esc" => Named::Escape,
+        "tab" => Named::Tab,
+        "space" => Named::Space,
+        "backspace" => Named::Backspace,
+        "up" => Named::ArrowUp,
+        "down" => Named::ArrowDown,
+        "left" => Named::ArrowLeft,
+        "right" => Named::ArrowRight,
+        "pageup" => Named::PageUp,
+        "pagedown" => Named::PageDown,
+        "home" => Named::Home,
+        "end" => Named::End,
+        _ => return None,
+    })
+}
+
+/// Parses a key sequence such as `g g`, `gg`, `G` or `ctrl-c`. A word that
+/// is not a key name or modifier combination is a sequence of characters.
+fn parse_keys(keys: &str) -> Result<Vec<Chord>, Error> {
+    let mut chords = Vec::new();
+    for word in keys.split_whitespace() {
+        let mut modifiers = Modifiers::empty();
+        let mut rest = word;
+        while let Some((modifier, key)) = rest.split_once('-').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier {
+                "ctrl" => Modifiers::CTRL,
+                "alt" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                _ => return Err(Error::Parse(format!("unknown modifier in: {word}"))),
+            };
+            rest = key;
+        }
+        if let Some(name) = named(rest) {
+            chords.push(Chord::new(Key::Named(name), modifiers));
+        } else if modifiers.is_empty() {
+            chords.extend(
+                rest.chars()
+                    .map(|c| Chord::new(Key::Character(c.to_string().into()), modifiers)),
+            );
+        } else if rest.chars().count() == 1 {
+            chords.push(Chord::new(Key::Character(rest.into()), modifiers));
+        } else {
+            return Err(Error::Parse(format!("unknown key: {word}")));
+        }
+    }
+    if chords.is_empty() {
+        return Err(Error::Parse("empty key binding".to_string()));
+    }
+    Ok(chords)
+}
+
+/// Modal key bindings, vi and helix flavoured by default. Multi key
+/// sequences like `gg` wait for the next press before resolving.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Vec<Chord>, Command)>,
+    pending: Vec<Chord>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self::empty();
+        for (keys, command) in [
+            ("j", Command::SelectNext),
+            ("down", Command::SelectNext),
+            ("k", Command::SelectPrevious),
+            ("up", Command::SelectPrevious),
+            ("enter", Command::Run),
+            ("o", Command::RunWithOptions),
+            ("x", Command::Stop),
+            ("ctrl-c", Command::Stop),
+            ("gn", Command::NextTab),
+            ("gp", Command::PreviousTab),
+            ("gg", Command::ScrollToBeginning),
+            ("G", Command::ScrollToEnd),
+            ("ge", Command::ScrollToEnd),
+            ("/", Command::Search),
+            ("H", Command::History),
+            ("i", Command::Input),
+            ("ctrl-d", Command::SendEof),
+            ("?", Command::SearchOutput),
+            ("n", Command::SearchNext),
+            ("N", Command::SearchPrevious),
+        ] {
+            keymap
+                .bind(keys, command)
+                .expect("default key bindings parse");
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    pub fn empty() -> Self {
+        Self {
+            bindings: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Binds `keys` to `command`, replacing an existing binding of `keys`.
+    pub fn bind(&mut self, keys: &str, command: Command) -> Result<(), Error> {
+        let chords = parse_keys(keys)?;
+        self.bindings.retain(|(bound, _)| *bound != chords);
+        self.bindings.push((chords, command));
+        Ok(())
+    }
+
+    /// Feeds a key press, returning the command once a binding is complete.
+    pub fn press(&mut self, key: Key, modifiers: Modifiers) -> Option<Command> {
+        if matches!(
+            key,
+            Key::Named(Named::Shift | Named::Control | Named::Alt | Named::Super)
+        ) {
+            return None;
+        }
+        self.pending.push(Chord::new(key, modifiers));
+        loop {
+            if let Some((_, command)) = self.bindings.iter().find(|(keys, _)| *keys == self.pending)
+            {
+                let command = *command;
+                self.pending.clear();
+                return Some(command);
+            }
+            if self
+                .bindings
+                .iter()
+                .any(|(keys, _)| keys.starts_with(&self.pending))
+            {
+                return None;
+            }
+            // A sequence that went nowhere starts over from its last key.
+            if self.pending.len() > 1 {
+                self.pending.drain(..self.pending.len() - 1);
+            } else {
+                self.pending.clear();
+                return None;
+            }
+        }
+    }
+
+    /// Forgets a half typed sequence.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(keymap: &mut Keymap, c: &str, modifiers: Modifiers) -> Option<Command> {
+        keymap.press(Key::Character(c.into()), modifiers)
+    }
+
+    #[test]
+    fn test_single_keys() {
+        let mut keymap = Keymap::default();
+        assert_eq!(
+            press(&mut keymap, "j", Modifiers::empty()),
+            Some(Command::SelectNext)
+        );
+        assert_eq!(
+            keymap.press(Key::Named(Named::Enter), Modifiers::empty()),
+            Some(Command::Run)
+        );
+        assert_eq!(
+            press(&mut keymap, "c", Modifiers::CTRL),
+            Some(Command::Stop)
+        );
+        assert_eq!(press(&mut keymap, "c", Modifiers::empty()), None);
+    }
+
+    #[test]
+    fn test_sequences() {
+        let mut keymap = Keymap::default();
+        assert_eq!(press(&mut keymap, "g", Modifiers::empty()), None);
+        assert_eq!(
+            press(&mut keymap, "g", Modifiers::empty()),
+            Some(Command::ScrollToBeginning)
+        );
+        assert_eq!(
+            press(&mut keymap, "g", Modifiers::SHIFT),
+            Some(Command::ScrollToEnd)
+        );
+        // an unbound sequence retries its last key on its own
+        assert_eq!(press(&mut keymap, "g", Modifiers::empty()), None);
+        assert_eq!(
+            press(&mut keymap, "k", Modifiers::empty()),
+            Some(Command::SelectPrevious)
+        );
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut keymap = Keymap::default();
+        keymap.bind("ctrl-r", Command::Run).unwrap();
+        keymap.bind("enter", Command::Stop).unwrap();
+        assert_eq!(press(&mut keymap, "r", Modifiers::CTRL), Some(Command::Run));
+        assert_eq!(
+            keymap.press(Key::Named(Named::Enter), Modifiers::empty()),
+            Some(Command::Stop)
+        );
+        assert_eq!("scroll-to-end".parse(), Ok(Command::ScrollToEnd));
+        assert!(keymap.bind("hyper-x", Command::Run).is_err());
+        assert!("jump".parse::<Command>().is_err());
+    }
+}
@@ -2,110 +2,259 @@ extern crate pretty_env_logger;
 #[macro_use]
 extern crate log;
 
-mod args;
 mod icons;
+mod keymap;
 mod stdout;
-mod task_runners;
-mod utils;
 mod widgets;
 
-use args::parse_args;
+use cracker_core::args::{parse_args_or_exit, Args, Program};
+use cracker_core::config::{self, Config};
+use cracker_core::fuzzy::Match;
+use cracker_core::history::{History, Record};
+use cracker_core::search::{Query, Searcher};
+use cracker_core::task_runners::{self, Discovery, Group, Target, TargetFilter};
+use cracker_core::utils::Error;
 use iced::alignment::Horizontal::Left;
-use iced::widget::{self, column, horizontal_space, pick_list, row, scrollable, Column};
+use iced::keyboard::{self, key::Named, Key, Modifiers};
+use iced::widget::{
+    button, checkbox, column, horizontal_space, pick_list, row, scrollable, text, text_input,
+    Column, Row,
+};
 use iced::Alignment::Center;
 use iced::Length::Fill;
 use iced::{Element, Font, Subscription, Task, Theme};
 use itertools::Itertools;
+use keymap::{Command, Keymap};
 use once_cell::sync::Lazy;
+use std::collections::HashMap;
 use std::env;
-use stdout::worker::{self};
-use task_runners::makefile::parser;
-use utils::{async_read_lines, Error};
-use widgets::stdoutput::StdOutput;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use stdout::worker::{self, RunOptions, StdCommand, StopPolicy};
+use widgets::run_dialog::{self, RunDialog};
+use widgets::stdoutput::{StdOutput, StreamFilter, LINE_HEIGHT};
 use widgets::target_list;
 
 use std::fmt::Debug;
-use std::sync::Arc;
+
+const PROGRAM: Program = Program {
+    name: env!("CARGO_PKG_NAME"),
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+};
+
+/// Search score a run adds, up to 5 for frequency and 10 for recency.
+const RUN_BOOST: i64 = 4;
+
+/// Search score a favourite target gets.
+const FAVOURITE_BOOST: i64 = 20;
+
+/// Width of a character of the output text, 16px monospace.
+const CHAR_WIDTH: f32 = 9.6;
+
+/// Space around the output in its pane.
+const OUTPUT_PADDING: f32 = 40.0;
 
 static SCROLLABLE_ID: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
+static TARGETS_ID: Lazy<scrollable::Id> = Lazy::new(scrollable::Id::unique);
+static SEARCH_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static RUN_DIALOG_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+static OUTPUT_SEARCH_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
 
 pub fn main() -> iced::Result {
     pretty_env_logger::init();
     debug!("start ck");
 
-    let filename = match parse_args(&env::args().collect_vec()) {
-        Ok(f) => {
-            debug!("file returned: {}", f);
-            f
-        }
-        Err(args::Error::CliExit) => {
-            debug!("exit by cli");
-            return Ok(());
-        }
-    };
+    let args = parse_args_or_exit(&PROGRAM, &env::args().collect_vec());
+    debug!("file returned: {:?}", args.filename);
 
     iced::application("Editor - Iced", Editor::update, Editor::view)
         .subscription(Editor::subscription)
         .theme(Editor::theme)
         .font(include_bytes!("../fonts/editor-icons.ttf").as_slice())
         .default_font(Font::MONOSPACE)
-        .run_with(move || Editor::new(filename))
+        .run_with(move || Editor::new(args))
 }
 
 #[derive(Debug)]
 struct Editor {
-    filename: String,
+    filename: Option<String>,
+    discovery: Discovery,
+    directory: Option<PathBuf>,
+    stop_policy: StopPolicy,
     theme: Theme,
-    targets: Vec<String>,
+    groups: Vec<Group>,
     task_history: Vec<StdOutput>,
+    runs: usize,
+    stream_filter: StreamFilter,
+    keymap: Keymap,
+    selected: usize,
+    search: String,
+    target_filter: TargetFilter,
+    /// Options last used to run a target, by its command line.
+    run_options: HashMap<String, RunOptions>,
+    /// Run tasks in a pseudo-terminal unless their run options say not to.
+    pty: bool,
+    /// Columns and rows of the output pane, once it has been laid out.
+    terminal_size: Option<(u16, u16)>,
+    /// Height of the output pane, the part of the output laid out.
+    output_height: f32,
+    run_dialog: Option<RunDialog>,
+    /// Runs with a tab in the output view, in the order they were opened.
+    tabs: Vec<usize>,
+    /// Where finished runs are saved, when there is a data directory.
+    history: Option<History>,
+    /// Runs saved in earlier sessions and this one, oldest first.
+    past_runs: Vec<Record>,
+    history_open: bool,
+    /// Run whose output is shown.
+    active_tab: Option<usize>,
+    /// What to look for in the output shown, and why it can't be searched.
+    output_search: Query,
+    output_search_error: Option<String>,
 
-    auto_scroll: bool,
-    scrollbar_width: u16,
-    scrollbar_margin: u16,
-    scroller_width: u16,
-    current_scroll_offset: scrollable::RelativeOffset,
+    config: Config,
+    /// Config files, watched for changes, and when they were last modified.
+    config_files: Vec<PathBuf>,
+    config_modified: Vec<Option<SystemTime>>,
+    config_errors: Vec<String>,
     anchor: scrollable::Anchor,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
-    LoadMakeTargetsPEG,
-    ParseMakeTargets(std::result::Result<Arc<String>, Error>),
+    LoadTargets,
+    TargetsLoaded(std::result::Result<Vec<Group>, Error>),
     Reload,
-    TaskMake(usize, String),
+    TaskMake(usize, StdCommand),
     TaskUpdate((usize, Result<worker::Stdout, worker::Error>)),
     TaskStop(usize),
+    TaskStopRun(usize),
+    TaskInputChanged(usize, String),
+    TaskInputSubmit(usize),
+    TaskInputEof(usize),
+    TabSelected(usize),
+    TabClosed(usize),
+    HistoryLoad,
+    HistoryLoaded(Result<Vec<Record>, Error>),
+    RunOptionsLoaded(Result<HashMap<String, RunOptions>, Error>),
+    RunOptionsSaved(Result<(), Error>),
+    HistorySaved(Result<Record, Error>),
+    HistoryToggle,
+    HistoryOpen(Record),
+    HistoryOutput(Record, Result<Vec<worker::Line>, Error>),
+    HistoryRerun(Record),
     ThemeSelected(Theme),
+    StreamFilterSelected(StreamFilter),
+    OutputSearchChanged(String),
+    OutputSearchCase(bool),
+    OutputSearchRegex(bool),
+    OutputSearchNext,
+    OutputSearchPrevious,
+    KeyPressed(Key, Modifiers),
+    SearchChanged(String),
+    SearchSubmitted,
+    TargetFilterChanged(TargetFilter),
+    TargetSelected(usize),
+    RunDialogOpen(usize, StdCommand),
+    RunDialogChanged(run_dialog::Field),
+    RunDialogSubmit,
+    RunDialogClose,
 
     ScrollToBeginning,
     ScrollToEnd,
     ScrollAutoToggle,
     Scrolled(scrollable::Viewport),
+    ConfigCheck,
 }
 
 impl Editor {
-    fn new(filename: String) -> (Self, Task<Message>) {
+    fn new(args: Args) -> (Self, Task<Message>) {
+        // the project is where tasks run, or where the build file is
+        let project_dir = match (&args.directory, &args.filename) {
+            (Some(directory), _) => directory.clone(),
+            (None, Some(file)) => PathBuf::from(file)
+                .parent()
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+            (None, None) => PathBuf::new(),
+        };
+        let config_files = Config::files(&project_dir);
+        let mut editor = Self {
+            filename: args.filename,
+            discovery: args.discovery,
+            directory: args.directory,
+            stop_policy: args.stop_policy,
+            theme: Theme::CatppuccinMocha,
+            groups: Vec::new(),
+            task_history: Vec::new(),
+            runs: 0,
+            stream_filter: StreamFilter::default(),
+            keymap: Keymap::default(),
+            selected: 0,
+            search: String::new(),
+            target_filter: TargetFilter::default(),
+            run_options: HashMap::new(),
+            pty: args.pty,
+            terminal_size: None,
+            output_height: 1_000.0,
+            run_dialog: None,
+            tabs: Vec::new(),
+            history: History::open(),
+            past_runs: Vec::new(),
+            history_open: false,
+            active_tab: None,
+            output_search: Query::default(),
+            output_search_error: None,
+
+            config: Config::default(),
+            config_files,
+            config_modified: Vec::new(),
+            config_errors: Vec::new(),
+            anchor: scrollable::Anchor::Start,
+        };
+        editor.load_config();
         (
-            Self {
-                filename,
-                theme: Theme::CatppuccinMocha,
-                targets: Vec::new(),
-                task_history: Vec::new(),
-
-                auto_scroll: true,
-                scrollbar_width: 15,
-                scrollbar_margin: 0,
-                scroller_width: 10,
-                current_scroll_offset: scrollable::RelativeOffset::END,
-                anchor: scrollable::Anchor::Start,
-            },
+            editor,
             Task::batch([
-                Task::done(Message::LoadMakeTargetsPEG),
-                widget::focus_next(),
+                Task::done(Message::Reload),
+                Task::done(Message::HistoryLoad),
             ]),
         )
     }
 
+    /// Reads the config files and applies them, keeping the errors to show.
+    fn load_config(&mut self) {
+        self.config_modified = config::modified(&self.config_files);
+        let (config, errors) = Config::load(&self.config_files);
+        self.config_errors = errors.iter().map(|error| format!("{error:?}")).collect();
+        if let Some(name) = &config.theme {
+            let normalized = |name: &str| name.to_lowercase().replace([' ', '-', '_'], "");
+            match Theme::ALL
+                .iter()
+                .find(|theme| normalized(&theme.to_string()) == normalized(name))
+            {
+                Some(theme) => self.theme = theme.clone(),
+                None => self.config_errors.push(format!("unknown theme: {name}")),
+            }
+        }
+        self.keymap = Keymap::default();
+        for (name, keys) in &config.keys {
+            let bound = name.parse::<Command>().and_then(|command| {
+                keys.iter()
+                    .try_for_each(|keys| self.keymap.bind(keys, command))
+            });
+            if let Err(error) = bound {
+                self.config_errors.push(format!("keys.{name}: {error:?}"));
+            }
+        }
+        for error in &self.config_errors {
+            warn!("config: {error}");
+        }
+        self.config = config;
+    }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::ThemeSelected(theme) => {
@@ -113,105 +262,639 @@ impl Editor {
 
                 Task::none()
             }
-            Message::TaskMake(id, target) => {
-                fn trim_task_history(tasks: &mut Vec<StdOutput>) {
-                    if tasks.len() >= 100 {
-                        let r = tasks.len() - 100;
-                        tasks.drain(..r);
-                    }
+            Message::StreamFilterSelected(filter) => {
+                self.stream_filter = filter;
+                self.search_output();
+
+                Task::none()
+            }
+            Message::OutputSearchChanged(text) => {
+                self.output_search.text = text;
+                self.search_output();
+                Task::none()
+            }
+            Message::OutputSearchCase(case_sensitive) => {
+                self.output_search.case_sensitive = case_sensitive;
+                self.search_output();
+                Task::none()
+            }
+            Message::OutputSearchRegex(regex) => {
+                self.output_search.regex = regex;
+                self.search_output();
+                Task::none()
+            }
+            Message::OutputSearchNext | Message::OutputSearchPrevious => {
+                let forward = matches!(message, Message::OutputSearchNext);
+                // the match is scrolled to the middle of the pane
+                let middle = (self.output_height - LINE_HEIGHT) / 2.0;
+                match self.active_task().and_then(|task| task.next_match(forward)) {
+                    Some(y) => scrollable::scroll_to(
+                        SCROLLABLE_ID.clone(),
+                        scrollable::AbsoluteOffset {
+                            x: 0.0,
+                            y: (OUTPUT_PADDING + y - middle).max(0.0),
+                        },
+                    ),
+                    None => Task::none(),
                 }
-                if let Some(task) = self.task_history.last_mut() {
-                    if task.id() == id {
-                        task.stop();
-                    }
+            }
+            // The run dialog takes the keyboard while it is open.
+            Message::KeyPressed(Key::Named(Named::Escape), _) if self.run_dialog.is_some() => {
+                Task::done(Message::RunDialogClose)
+            }
+            Message::KeyPressed(Key::Named(Named::Escape), _) if self.history_open => {
+                Task::done(Message::HistoryToggle)
+            }
+            Message::KeyPressed(..) if self.run_dialog.is_some() || self.history_open => {
+                Task::none()
+            }
+            Message::KeyPressed(key, modifiers) => match self.keymap.press(key, modifiers) {
+                Some(command) => self.command(command),
+                None => Task::none(),
+            },
+            Message::RunDialogOpen(id, command) => {
+                let options = self.run_options(&command);
+                self.run_dialog = Some(RunDialog::new(id, command, &options));
+                self.keymap.reset();
+                text_input::focus(RUN_DIALOG_ID.clone())
+            }
+            Message::RunDialogChanged(field) => {
+                if let Some(dialog) = &mut self.run_dialog {
+                    dialog.update(field);
+                }
+                Task::none()
+            }
+            Message::RunDialogSubmit => {
+                let Some(dialog) = &self.run_dialog else {
+                    return Task::none();
                 };
-                let mut task = StdOutput::new(id, target);
-                task.start();
-                self.task_history.push(task);
-                trim_task_history(&mut self.task_history);
+                // The dialog shows why invalid options can't be run.
+                let Ok(options) = dialog.options() else {
+                    return Task::none();
+                };
+                let (id, command) = (dialog.id(), dialog.command().clone());
+                self.run_options.insert(command.command_line(), options);
+                self.run_dialog = None;
+                let run = Task::done(Message::TaskMake(id, command));
+                match self.history.clone() {
+                    Some(history) => {
+                        let options = self.run_options.clone();
+                        let save = Task::perform(
+                            async move { history.save_run_options(&options).await },
+                            Message::RunOptionsSaved,
+                        );
+                        Task::batch([run, save])
+                    }
+                    None => run,
+                }
+            }
+            Message::RunDialogClose => {
+                self.run_dialog = None;
+                Task::none()
+            }
+            Message::TargetFilterChanged(filter) => {
+                self.target_filter = filter;
+                self.select_visible();
 
                 Task::none()
             }
+            Message::SearchChanged(search) => {
+                self.search = search;
+                // the best match is selected, enter runs it
+                self.selected = self.visible_targets().first().copied().unwrap_or(0);
+
+                Task::none()
+            }
+            Message::TargetSelected(id) => {
+                self.selected = id;
+                Task::none()
+            }
+            // Focusing an id no widget has leaves normal mode keys unfocused.
+            Message::SearchSubmitted => text_input::focus(text_input::Id::unique()),
+            Message::TaskMake(id, mut command) => {
+                let options = self.run_options(&command);
+                command = command.with_options(options);
+                let args = [command.args(), self.config.args(command.program())].concat();
+                command = command.with_args(args);
+                self.start(id, command)
+            }
             Message::TaskStop(id) => {
                 debug!("stop id: {id:?}");
+                for task in self.task_history.iter_mut().filter(|t| t.id() == id) {
+                    task.stop();
+                }
 
-                if let Some(task) = self.task_history.last_mut() {
-                    if task.id() == id {
-                        task.stop();
-                    }
+                Task::none()
+            }
+            Message::TaskInputChanged(run, input) => {
+                if let Some(task) = self.task(run) {
+                    task.set_input(input);
+                }
+                Task::none()
+            }
+            Message::TaskInputSubmit(run) => {
+                if let Some(task) = self.task(run) {
+                    task.send_input();
+                }
+                Task::none()
+            }
+            Message::TaskInputEof(run) => {
+                if let Some(task) = self.task(run) {
+                    task.send_eof();
+                }
+                Task::none()
+            }
+            Message::TaskStopRun(run) => {
+                if let Some(task) = self.task(run) {
+                    task.stop();
                 }
 
-                debug!("task stop??");
                 Task::none()
             }
-            Message::TaskUpdate((id, output)) => {
+            Message::TaskUpdate((run, output)) => {
+                let finished = matches!(output, Ok(worker::Stdout::Finished(_)));
+                let history = self.history.clone();
+                let limit = self.config.history_limit;
+                let active = self.active_tab == Some(run);
                 let mut next_task = Task::none();
-                if let Some(task) = self.task_history.last_mut() {
-                    if task.id() == id {
-                        task.stream_update(output);
-                    }
+                if let Some(task) = self.task(run) {
+                    task.stream_update(output);
 
-                    if self.auto_scroll {
+                    if task.auto_scroll() && active {
                         next_task = Task::done(Message::ScrollToEnd);
                     }
+                    if let (true, Some(history), Some(exit)) = (finished, history, task.exit()) {
+                        let (command, exit) = (task.command().clone(), exit.clone());
+                        let output = task.output().to_vec();
+                        let save = Task::perform(
+                            async move { history.save(command, exit, &output, limit).await },
+                            Message::HistorySaved,
+                        );
+                        next_task = Task::batch([next_task, save]);
+                    }
                 }
 
                 next_task
             }
-            Message::ScrollAutoToggle => {
-                self.auto_scroll = !self.auto_scroll;
-                let offset = scrollable::AbsoluteOffset { x: 0.00, y: 59.0 };
-                scrollable::scroll_by(SCROLLABLE_ID.clone(), offset)
+            Message::HistoryLoad => match self.history.clone() {
+                Some(history) => {
+                    let options = history.clone();
+                    Task::batch([
+                        Task::perform(async move { history.load().await }, Message::HistoryLoaded),
+                        Task::perform(
+                            async move { options.load_run_options().await },
+                            Message::RunOptionsLoaded,
+                        ),
+                    ])
+                }
+                None => Task::none(),
+            },
+            Message::RunOptionsLoaded(result) => {
+                match result {
+                    // options set in this session before the load came back win
+                    Ok(saved) => {
+                        for (command_line, options) in saved {
+                            self.run_options.entry(command_line).or_insert(options);
+                        }
+                    }
+                    Err(error) => warn!("failed to load run options: {error:?}"),
+                }
+                Task::none()
+            }
+            Message::RunOptionsSaved(result) => {
+                if let Err(error) = result {
+                    warn!("failed to save run options: {error:?}");
+                }
+                Task::none()
+            }
+            Message::HistoryLoaded(result) => {
+                match result {
+                    Ok(records) => {
+                        // runs of this session saved before the load came back
+                        // are kept after the earlier ones
+                        let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+                        self.past_runs.retain(|r| !ids.contains(&r.id.as_str()));
+                        self.past_runs.splice(..0, records);
+                    }
+                    Err(error) => warn!("failed to load history: {error:?}"),
+                }
+                Task::none()
+            }
+            Message::HistorySaved(result) => {
+                match result {
+                    Ok(record) => {
+                        self.past_runs.push(record);
+                        let r = self
+                            .past_runs
+                            .len()
+                            .saturating_sub(self.config.history_limit);
+                        self.past_runs.drain(..r);
+                    }
+                    Err(error) => warn!("failed to save run to history: {error:?}"),
+                }
+                Task::none()
+            }
+            Message::HistoryToggle => {
+                self.history_open = !self.history_open;
+                self.keymap.reset();
+                Task::none()
             }
-            Message::Reload => Task::done(Message::LoadMakeTargetsPEG),
-            Message::LoadMakeTargetsPEG => {
-                self.targets.clear();
+            Message::HistoryOpen(record) => {
+                self.history_open = false;
+                match self.history.clone() {
+                    Some(history) => Task::perform(
+                        async move {
+                            let output = history.output(&record).await;
+                            (record, output)
+                        },
+                        |(record, output)| Message::HistoryOutput(record, output),
+                    ),
+                    None => Task::none(),
+                }
+            }
+            Message::HistoryOutput(record, output) => {
+                let output = output.unwrap_or_else(|error| {
+                    warn!("failed to read output of run {}: {error:?}", record.id);
+                    vec![worker::Line::stderr(format!(
+                        "output unavailable: {error:?}"
+                    ))]
+                });
+                self.runs += 1;
+                let id = self.target_id(&record.command);
+                let task = StdOutput::restored(id, self.runs, record, output)
+                    .with_max_lines(self.config.max_output_lines);
+                self.task_history.push(task);
+                self.trim_task_history();
+                self.tabs.push(self.runs);
 
-                Task::perform(
-                    async_read_lines(self.filename.clone()),
-                    Message::ParseMakeTargets,
-                )
+                self.show_tab(self.runs)
             }
-            Message::ParseMakeTargets(result) => {
-                if let Ok(contents) = result {
-                    for line in contents.lines() {
-                        let target = parser::Targets(line);
-                        if let Ok(t) = target {
-                            self.targets.extend(t);
-                        }
+            Message::HistoryRerun(record) => {
+                self.history_open = false;
+                // the saved command already has its options and arguments
+                let id = self.target_id(&record.command);
+                self.start(id, record.command)
+            }
+            Message::TabSelected(run) => self.show_tab(run),
+            Message::TabClosed(run) => {
+                if let Some(task) = self.task(run) {
+                    task.stop();
+                }
+                let Some(position) = self.tabs.iter().position(|tab| *tab == run) else {
+                    return Task::none();
+                };
+                self.tabs.remove(position);
+                if self.active_tab != Some(run) {
+                    return Task::none();
+                }
+                // the tab taking the closed one's place is shown
+                match self.tabs.get(position).or(self.tabs.last()) {
+                    Some(&next) => self.show_tab(next),
+                    None => {
+                        self.active_tab = None;
+                        Task::none()
                     }
                 }
+            }
+            Message::ScrollAutoToggle => match self.active_task() {
+                Some(task) => {
+                    task.toggle_auto_scroll();
+                    let offset = scrollable::AbsoluteOffset { x: 0.00, y: 59.0 };
+                    scrollable::scroll_by(SCROLLABLE_ID.clone(), offset)
+                }
+                None => Task::none(),
+            },
+            Message::Reload => Task::done(Message::LoadTargets),
+            Message::LoadTargets => Task::perform(
+                task_runners::groups(
+                    self.filename.as_ref().map(PathBuf::from),
+                    self.discovery,
+                    self.directory.clone(),
+                ),
+                Message::TargetsLoaded,
+            ),
+            Message::TargetsLoaded(result) => {
+                match result {
+                    Ok(groups) => self.groups = groups,
+                    Err(error) => {
+                        warn!("failed to load targets: {error:?}");
+                        self.groups.clear();
+                    }
+                }
+                self.select_visible();
 
                 Task::none()
             }
 
             Message::ScrollToBeginning => {
-                self.current_scroll_offset = scrollable::RelativeOffset::START;
+                if let Some(task) = self.active_task() {
+                    task.scrolled(scrollable::RelativeOffset::START, 0.0);
+                }
 
-                scrollable::snap_to(SCROLLABLE_ID.clone(), self.current_scroll_offset)
+                scrollable::snap_to(SCROLLABLE_ID.clone(), scrollable::RelativeOffset::START)
             }
             Message::ScrollToEnd => {
-                self.current_scroll_offset = scrollable::RelativeOffset::END;
+                if let Some(task) = self.active_task() {
+                    task.scrolled(scrollable::RelativeOffset::END, f32::INFINITY);
+                }
 
-                scrollable::snap_to(SCROLLABLE_ID.clone(), self.current_scroll_offset)
+                scrollable::snap_to(SCROLLABLE_ID.clone(), scrollable::RelativeOffset::END)
+            }
+            Message::ConfigCheck => {
+                if config::modified(&self.config_files) != self.config_modified {
+                    info!("config changed, reloading");
+                    self.load_config();
+                }
+                Task::none()
             }
             Message::Scrolled(viewport) => {
-                self.current_scroll_offset = viewport.relative_offset();
+                let top = viewport.absolute_offset().y - OUTPUT_PADDING;
+                if let Some(task) = self.active_task() {
+                    task.scrolled(viewport.relative_offset(), top);
+                }
+                // tasks in a terminal get the size of the pane, less its padding
+                let bounds = viewport.bounds();
+                self.output_height = bounds.height;
+                let width =
+                    bounds.width - 2.0 * OUTPUT_PADDING - f32::from(self.config.scrollbar_width);
+                let size = (
+                    (width / CHAR_WIDTH).max(1.0) as u16,
+                    ((bounds.height - 2.0 * OUTPUT_PADDING) / LINE_HEIGHT).max(1.0) as u16,
+                );
+                if self.terminal_size != Some(size) {
+                    self.terminal_size = Some(size);
+                    for task in &mut self.task_history {
+                        task.resize(size);
+                    }
+                }
 
                 Task::none()
             }
         }
     }
-    fn subscription(&self) -> Subscription<Message> {
-        if self.task_history.is_empty() {
-            return Subscription::none();
+
+    /// Id of the listed target `command` runs, or one no target has.
+    fn target_id(&self, command: &StdCommand) -> usize {
+        self.targets()
+            .position(|target| {
+                let listed = &target.command;
+                listed.program() == command.program()
+                    && listed.args() == command.args()
+                    && listed.target() == command.target()
+                    && listed.dir() == command.dir()
+            })
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Runs `command` as is in a new tab.
+    fn start(&mut self, id: usize, command: StdCommand) -> Task<Message> {
+        self.runs += 1;
+        let mut command = command.with_stop_policy(self.stop_policy);
+        if let Some(size) = self.terminal_size {
+            command = command.with_terminal_size(size);
+        }
+        let mut task =
+            StdOutput::new(id, self.runs, command).with_max_lines(self.config.max_output_lines);
+        task.start();
+        self.task_history.push(task);
+        self.trim_task_history();
+        self.tabs.push(self.runs);
+
+        self.show_tab(self.runs)
+    }
+
+    /// Options remembered for `command`, or the defaults.
+    fn run_options(&self, command: &StdCommand) -> RunOptions {
+        self.run_options
+            .get(&command.command_line())
+            .cloned()
+            .unwrap_or_else(|| RunOptions {
+                pty: self.pty || self.config.pty,
+                ..RunOptions::default()
+            })
+    }
+
+    fn favourite(&self, target: &Target) -> bool {
+        self.config
+            .favourites
+            .iter()
+            .any(|name| name == target.name())
+    }
+
+    fn task(&mut self, run: usize) -> Option<&mut StdOutput> {
+        self.task_history.iter_mut().rev().find(|t| t.run() == run)
+    }
+
+    fn active_task(&mut self) -> Option<&mut StdOutput> {
+        self.task(self.active_tab?)
+    }
+
+    /// Searches the output shown for the output search.
+    fn search_output(&mut self) {
+        let searcher = match Searcher::new(&self.output_search) {
+            Ok(searcher) => {
+                self.output_search_error = None;
+                searcher
+            }
+            Err(Error::Parse(error)) => {
+                self.output_search_error = Some(error);
+                None
+            }
+            Err(error) => {
+                self.output_search_error = Some(format!("{error:?}"));
+                None
+            }
+        };
+        let filter = self.stream_filter;
+        if let Some(task) = self.active_task() {
+            task.search(searcher, filter);
+        }
+    }
+
+    /// Shows the output of `run`, scrolled to where it was left.
+    fn show_tab(&mut self, run: usize) -> Task<Message> {
+        self.active_tab = Some(run);
+        self.search_output();
+        match self.task(run) {
+            Some(task) => scrollable::snap_to(SCROLLABLE_ID.clone(), task.offset()),
+            None => Task::none(),
+        }
+    }
+
+    /// Drops the oldest finished runs past the history limit, with their
+    /// tabs. Running tasks are kept.
+    fn trim_task_history(&mut self) {
+        while self.task_history.len() > self.config.history_limit {
+            let Some(oldest) = self.task_history.iter().position(|t| !t.running()) else {
+                break;
+            };
+            self.task_history.remove(oldest);
+        }
+        let runs: Vec<usize> = self.task_history.iter().map(StdOutput::run).collect();
+        self.tabs.retain(|run| runs.contains(run));
+    }
+
+    /// Moves to the tab `step` places away, wrapping around.
+    fn cycle_tab(&mut self, step: isize) -> Task<Message> {
+        let count = self.tabs.len() as isize;
+        let Some(position) = self
+            .active_tab
+            .and_then(|run| self.tabs.iter().position(|tab| *tab == run))
+        else {
+            return Task::none();
+        };
+        let next = self.tabs[(position as isize + step).rem_euclid(count) as usize];
+        self.show_tab(next)
+    }
+
+    fn command(&mut self, command: Command) -> Task<Message> {
+        let visible = self.visible_targets();
+        let position = visible.iter().position(|id| *id == self.selected);
+        match command {
+            Command::SelectNext => {
+                if let Some(id) = position.map_or(visible.first(), |p| visible.get(p + 1)) {
+                    self.selected = *id;
+                }
+                Task::none()
+            }
+            Command::SelectPrevious => {
+                if let Some(id) =
+                    position.map_or(visible.first(), |p| visible.get(p.saturating_sub(1)))
+                {
+                    self.selected = *id;
+                }
+                Task::none()
+            }
+            Command::Run => match self.targets().nth(self.selected) {
+                Some(target) if position.is_some() => {
+                    Task::done(Message::TaskMake(self.selected, target.command.clone()))
+                }
+                _ => Task::none(),
+            },
+            Command::RunWithOptions => match self.targets().nth(self.selected) {
+                Some(target) if position.is_some() => Task::done(Message::RunDialogOpen(
+                    self.selected,
+                    target.command.clone(),
+                )),
+                _ => Task::none(),
+            },
+            Command::Stop => Task::done(Message::TaskStop(self.selected)),
+            Command::NextTab => self.cycle_tab(1),
+            Command::PreviousTab => self.cycle_tab(-1),
+            Command::ScrollToBeginning => Task::done(Message::ScrollToBeginning),
+            Command::ScrollToEnd => Task::done(Message::ScrollToEnd),
+            Command::History => Task::done(Message::HistoryToggle),
+            Command::Input => {
+                self.keymap.reset();
+                text_input::focus(INPUT_ID.clone())
+            }
+            Command::SendEof => match self.active_tab {
+                Some(run) => Task::done(Message::TaskInputEof(run)),
+                None => Task::none(),
+            },
+            Command::Search => {
+                self.keymap.reset();
+                text_input::focus(SEARCH_ID.clone())
+            }
+            Command::SearchOutput => {
+                self.keymap.reset();
+                text_input::focus(OUTPUT_SEARCH_ID.clone())
+            }
+            Command::SearchNext => Task::done(Message::OutputSearchNext),
+            Command::SearchPrevious => Task::done(Message::OutputSearchPrevious),
+        }
+    }
+
+    fn targets(&self) -> impl Iterator<Item = &Target> {
+        self.groups.iter().flat_map(|group| group.targets.iter())
+    }
+
+    /// Selected target with its group and the id of the group's first target.
+    fn selected_target(&self) -> Option<(&Group, usize, &Target)> {
+        let mut offset = 0;
+        for group in &self.groups {
+            if let Some(target) = group.targets.get(self.selected - offset) {
+                return Some((group, offset, target));
+            }
+            offset += group.targets.len();
         }
-        Subscription::batch(self.task_history.iter().map(StdOutput::subscription))
+        None
+    }
+
+    /// Boost of a search result for how often and how recently it ran.
+    fn run_boost(&self, id: usize) -> i64 {
+        let runs = self.task_history.iter().filter(|t| t.id() == id);
+        let count = runs.clone().count() as i64;
+        let age = runs
+            .map(|t| self.runs - t.run())
+            .min()
+            .map_or(0, |age| 10 - (age as i64).min(10));
+        RUN_BOOST * (count.min(5) + age)
+    }
+
+    /// Targets shown by the filter and search, with their match, favourites
+    /// first. While searching they are ranked by score, boosted by their
+    /// runs and for favourites.
+    fn search_results(&self) -> Vec<(usize, Match)> {
+        let mut results: Vec<(usize, Match)> = self
+            .targets()
+            .enumerate()
+            .filter(|(_, target)| self.target_filter.shows(target))
+            .filter_map(|(id, target)| {
+                let mut found = widgets::search_match(target, &self.search)?;
+                if self.favourite(target) {
+                    found.score += FAVOURITE_BOOST;
+                }
+                Some((id, found))
+            })
+            .collect();
+        if self.search.trim().is_empty() {
+            let targets: Vec<&Target> = self.targets().collect();
+            results.sort_by_key(|(id, _)| !self.favourite(targets[*id]));
+        } else {
+            for (id, found) in &mut results {
+                found.score += self.run_boost(*id);
+            }
+            results.sort_by_key(|(_, found)| std::cmp::Reverse(found.score));
+        }
+        results
+    }
+
+    /// Keeps the selection on a shown target.
+    fn select_visible(&mut self) {
+        let visible = self.visible_targets();
+        if !visible.contains(&self.selected) {
+            self.selected = visible.first().copied().unwrap_or(0);
+        }
+    }
+
+    /// Ids of the targets shown by the filter and search, in list order.
+    fn visible_targets(&self) -> Vec<usize> {
+        self.search_results()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
     }
 
-    fn view(&self) -> Element<Message> {
+    fn subscription(&self) -> Subscription<Message> {
+        let keys =
+            keyboard::on_key_press(|key, modifiers| Some(Message::KeyPressed(key, modifiers)));
+        // config files are polled, a changed one is read again
+        let config = iced::time::every(Duration::from_secs(1)).map(|_| Message::ConfigCheck);
+        Subscription::batch(
+            [keys, config]
+                .into_iter()
+                .chain(self.task_history.iter().map(StdOutput::subscription)),
+        )
+    }
+
+    fn view(&self) -> Element<'_, Message> {
         let controls = row![
             widgets::action(icons::reload_icon(), "reload", Some(Message::Reload)),
+            button(text("history").size(14))
+                .style(button::secondary)
+                .padding([5, 10])
+                .on_press(Message::HistoryToggle),
             horizontal_space(),
             pick_list(Theme::ALL, Some(self.theme.clone()), Message::ThemeSelected)
                 .text_size(14)
@@ -241,8 +924,57 @@ impl Editor {
             )
         };
 
+        let (match_count, current_match) = self
+            .task_history
+            .iter()
+            .rev()
+            .find(|t| Some(t.run()) == self.active_tab)
+            .map_or((0, None), StdOutput::matches);
+        let search_output = row![
+            text_input("? search output", &self.output_search.text)
+                .id(OUTPUT_SEARCH_ID.clone())
+                .on_input(Message::OutputSearchChanged)
+                .on_submit(Message::OutputSearchNext)
+                .size(14)
+                .width(200),
+            checkbox("Aa", self.output_search.case_sensitive)
+                .on_toggle(Message::OutputSearchCase)
+                .text_size(14),
+            checkbox(".*", self.output_search.regex)
+                .on_toggle(Message::OutputSearchRegex)
+                .text_size(14),
+            match &self.output_search_error {
+                Some(error) => text(error).size(12).style(text::danger),
+                None => match current_match {
+                    Some(current) => text!("{}/{match_count}", current + 1),
+                    None => text!("{match_count}"),
+                }
+                .size(14),
+            },
+            widgets::action(
+                icons::up_icon(),
+                "previous match",
+                (match_count > 0).then_some(Message::OutputSearchPrevious),
+            ),
+            widgets::action(
+                icons::down_icon(),
+                "next match",
+                (match_count > 0).then_some(Message::OutputSearchNext),
+            ),
+        ]
+        .spacing(10)
+        .align_y(Center);
+
         let controls_output = row![
+            search_output,
             horizontal_space(),
+            pick_list(
+                StreamFilter::ALL,
+                Some(self.stream_filter),
+                Message::StreamFilterSelected
+            )
+            .text_size(14)
+            .padding([5, 10]),
             scroll_to_end_button(),
             scroll_auto_on_off_button(),
             scroll_to_beginning_button(),
@@ -250,30 +982,61 @@ impl Editor {
         .spacing(10)
         .padding(10);
 
-        let status = row![].spacing(10);
+        let status = Row::with_children(
+            self.config_errors
+                .iter()
+                .map(|error| text(error).size(12).style(text::danger).into()),
+        )
+        .spacing(10);
         let mut targets = Vec::new();
-        target_list(&self.targets, &mut targets);
-        let text_box: Column<Message> =
-            Column::with_children(self.task_history.iter().map(StdOutput::view));
+        target_list(
+            &self.groups,
+            &self.task_history,
+            self.selected,
+            &self.search_results(),
+            self.theme.palette().primary,
+            &self.config.favourites,
+            &mut targets,
+        );
+        let tasks = || {
+            self.tabs
+                .iter()
+                .filter_map(|run| self.task_history.iter().rev().find(|t| t.run() == *run))
+        };
+        let tabs = scrollable(
+            Row::with_children(tasks().map(|t| t.tab(self.active_tab == Some(t.run())))).spacing(5),
+        )
+        .direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::new().width(0).scroller_width(0),
+        ));
+        let active_task = tasks().find(|t| self.active_tab == Some(t.run()));
+        let text_box: Column<Message> = Column::with_children(
+            active_task.map(|t| t.view(self.stream_filter, self.output_height)),
+        );
+        let scrollbar = scrollable::Scrollbar::new()
+            .width(self.config.scrollbar_width)
+            .margin(self.config.scrollbar_margin)
+            .scroller_width(self.config.scroller_width)
+            .anchor(self.anchor);
         let scrollable_stdout: Element<Message> = Element::from(
-            scrollable(
-                column![text_box,]
-                    .align_x(Center)
-                    .padding([40, 40])
-                    .spacing(40),
-            )
-            .direction(scrollable::Direction::Vertical(
-                scrollable::Scrollbar::new()
-                    .width(self.scrollbar_width)
-                    .margin(self.scrollbar_margin)
-                    .scroller_width(self.scroller_width)
-                    .anchor(self.anchor),
-            ))
-            .width(Fill)
-            .height(Fill)
-            .id(SCROLLABLE_ID.clone())
-            .on_scroll(Message::Scrolled),
+            scrollable(text_box.padding(OUTPUT_PADDING))
+                .direction(scrollable::Direction::Both {
+                    vertical: scrollbar,
+                    horizontal: scrollbar,
+                })
+                .width(Fill)
+                .height(Fill)
+                .id(SCROLLABLE_ID.clone())
+                .on_scroll(Message::Scrolled),
         );
+        let mut output_pane = column![tabs].spacing(10);
+        if let Some(task) = active_task {
+            output_pane = output_pane.push(task.header());
+        }
+        output_pane = output_pane.push(scrollable_stdout);
+        if let Some(input) = active_task.and_then(StdOutput::input) {
+            output_pane = output_pane.push(input);
+        }
 
         let scrollable_targets: Element<Message> = Element::from(
             scrollable(
@@ -291,16 +1054,65 @@ impl Editor {
             ))
             // .width(Fill)
             .height(Fill)
-            .id(SCROLLABLE_ID.clone())
-            .on_scroll(Message::Scrolled),
+            .id(TARGETS_ID.clone()),
         );
 
-        let row_of_scrollables = row![scrollable_targets, scrollable_stdout,];
+        let search = text_input("/ search", &self.search)
+            .id(SEARCH_ID.clone())
+            .on_input(Message::SearchChanged)
+            .on_submit(Message::SearchSubmitted)
+            .size(14)
+            .width(200);
+        let filter = self.target_filter;
+        let filters = column![
+            checkbox("phony only", filter.phony_only)
+                .on_toggle(
+                    move |phony_only| Message::TargetFilterChanged(TargetFilter {
+                        phony_only,
+                        ..filter
+                    })
+                )
+                .text_size(12),
+            checkbox("hide _hidden", filter.hide_hidden)
+                .on_toggle(
+                    move |hide_hidden| Message::TargetFilterChanged(TargetFilter {
+                        hide_hidden,
+                        ..filter
+                    })
+                )
+                .text_size(12),
+            checkbox("hide object files", filter.hide_object_files)
+                .on_toggle(move |hide_object_files| {
+                    Message::TargetFilterChanged(TargetFilter {
+                        hide_object_files,
+                        ..filter
+                    })
+                })
+                .text_size(12),
+        ]
+        .spacing(5);
+
+        let mut targets_column = column![search, filters, scrollable_targets].spacing(10);
+        if let Some((group, offset, target)) = self.selected_target() {
+            targets_column = targets_column
+                .push(scrollable(widgets::dependency_graph(group, offset, target)).height(200));
+        }
 
-        column![controls, controls_output, row_of_scrollables, status,]
+        let row_of_scrollables = row![targets_column, output_pane];
+
+        let content = column![controls, controls_output, row_of_scrollables, status,]
             .spacing(10)
-            .padding(10)
-            .into()
+            .padding(10);
+
+        match &self.run_dialog {
+            Some(dialog) => widgets::modal(content, dialog.view(), Message::RunDialogClose),
+            None if self.history_open => widgets::modal(
+                content,
+                widgets::history::history_browser(&self.past_runs),
+                Message::HistoryToggle,
+            ),
+            None => content.into(),
+        }
     }
 
     fn theme(&self) -> Theme {
@@ -0,0 +1,67 @@
+use iced::widget::{button, column, container, horizontal_space, row, scrollable, text};
+use iced::{Element, Length::Fill};
+use std::time::SystemTime;
+
+use cracker_core::history::Record;
+use cracker_core::utils::format_duration;
+
+use super::stdoutput::exit_badge;
+use crate::Message;
+
+/// Saved runs, newest first, each with buttons to open its output or run
+/// it again.
+pub fn history_browser(records: &[Record]) -> Element<'_, Message> {
+    let now = SystemTime::now();
+    let runs = records.iter().rev().map(|record| {
+        let ago = now.duration_since(record.exit.ended).unwrap_or_default();
+        row![
+            column![
+                row![
+                    text(record.command.target()),
+                    exit_badge(&record.exit),
+                    text!("{} ago", format_duration(ago))
+                        .size(12)
+                        .style(text::secondary),
+                ]
+                .spacing(10)
+                .align_y(iced::Alignment::Center),
+                text(record.command.command_line())
+                    .size(12)
+                    .style(text::secondary),
+            ],
+            horizontal_space(),
+            button(text("open").size(14))
+                .style(button::secondary)
+                .on_press(Message::HistoryOpen(record.clone())),
+            button(text("re-run").size(14)).on_press(Message::HistoryRerun(record.clone())),
+        ]
+        .spacing(10)
+        .align_y(iced::Alignment::Center)
+        .into()
+    });
+    let list: Element<'_, Message> = if records.is_empty() {
+        text("no runs yet").style(text::secondary).into()
+    } else {
+        scrollable(column(runs).spacing(10).padding([0, 15])).into()
+    };
+    container(
+        column![
+            row![
+                text("history"),
+                horizontal_space(),
+                button(text("close").size(14))
+                    .style(button::secondary)
+                    .on_press(Message::HistoryToggle),
+            ]
+            .align_y(iced::Alignment::Center),
+            list,
+        ]
+        .spacing(10),
+    )
+    .style(container::bordered_box)
+    .padding(20)
+    .width(Fill)
+    .max_width(800)
+    .max_height(600)
+    .into()
+}
@@ -1,26 +1,43 @@
+pub mod history;
+pub mod run_dialog;
 pub mod stdoutput;
 
 use iced::{
-    widget::{button, container, row, tooltip},
-    Element, Theme,
+    widget::{
+        button, center, column, container, mouse_area, opaque, rich_text, row, span, stack, text,
+        text::Span, tooltip,
+    },
+    Color, Element, Theme,
 };
 
+use cracker_core::fuzzy::{fuzzy_match, Match};
+use cracker_core::task_runners::{Group, Target};
+
 use crate::{icons, Message};
+use stdoutput::StdOutput;
 
 pub fn target_card<'a, Message: Clone + 'a>(
     action: Element<'a, Message>,
-    label: &'a str,
+    label: Element<'a, Message>,
     other_action: Element<'a, Message>,
+    status: Element<'a, Message>,
+    selected: bool,
 ) -> Element<'a, Message> {
-    container(row![action, other_action, label].spacing(1))
-        .style(container::bordered_box)
+    container(row![action, other_action, label, status].spacing(1))
+        .style(move |theme: &Theme| {
+            let mut style = container::bordered_box(theme);
+            if selected {
+                style.border.color = theme.palette().primary;
+            }
+            style
+        })
         .padding(1)
         .into()
 }
 
 pub fn action<'a, Message: Clone + 'a>(
     content: impl Into<Element<'a, Message>>,
-    label: &'a str,
+    label: impl Into<Element<'a, Message>>,
     on_press: Option<Message>,
 ) -> Element<'a, Message> {
     let action = button(container(content).center_x(10));
@@ -38,8 +55,76 @@ pub fn action<'a, Message: Clone + 'a>(
     }
 }
 
+/// `content` over a dimmed `base`, pressing outside of it sends `on_blur`.
+pub fn modal<'a>(
+    base: impl Into<Element<'a, Message>>,
+    content: impl Into<Element<'a, Message>>,
+    on_blur: Message,
+) -> Element<'a, Message> {
+    stack![
+        base.into(),
+        opaque(
+            mouse_area(center(opaque(content)).style(|_theme| {
+                container::Style {
+                    background: Some(
+                        Color {
+                            a: 0.8,
+                            ..Color::BLACK
+                        }
+                        .into(),
+                    ),
+                    ..container::Style::default()
+                }
+            }))
+            .on_press(on_blur)
+        )
+    ]
+    .into()
+}
+
+/// Score a description match has below any match of a target name.
+const DESCRIPTION_MATCH: i64 = -1000;
+
+/// Fuzzy match of the search on the target name, or a case insensitive
+/// substring match on its description ranked below name matches.
+pub fn search_match(target: &Target, search: &str) -> Option<Match> {
+    fuzzy_match(search, target.name()).or_else(|| {
+        let search = search.to_lowercase();
+        target
+            .description
+            .as_ref()
+            .filter(|description| description.to_lowercase().contains(&search))
+            .map(|_| Match {
+                score: DESCRIPTION_MATCH,
+                positions: Vec::new(),
+            })
+    })
+}
+
+/// `name` with the characters at `positions` highlighted.
+fn highlighted<'a>(name: &'a str, positions: &[usize], color: Color) -> Element<'a, Message> {
+    let mut spans: Vec<Span<'a, Message>> = Vec::new();
+    let mut start = 0;
+    let mut matched = false;
+    for (i, (offset, _)) in name.char_indices().enumerate() {
+        let is_match = positions.contains(&i);
+        if is_match != matched && offset > start {
+            spans.push(span(&name[start..offset]).color_maybe(matched.then_some(color)));
+            start = offset;
+        }
+        matched = is_match;
+    }
+    spans.push(span(&name[start..]).color_maybe(matched.then_some(color)));
+    rich_text(spans).into()
+}
+
 pub fn target_list<'a>(
-    targets: &'a [String],
+    groups: &'a [Group],
+    task_history: &'a [StdOutput],
+    selected: usize,
+    results: &[(usize, Match)],
+    highlight: Color,
+    favourites: &[String],
     list: &mut Vec<
         iced_core::Element<
             'a,
@@ -49,15 +134,136 @@ pub fn target_list<'a>(
         >,
     >,
 ) {
-    for (id, target) in targets.iter().enumerate() {
-        list.push(target_card(
+    let mut offset = 0;
+    for group in groups {
+        let ids = offset..offset + group.targets.len();
+        let mut found = results.iter().filter(|(id, _)| ids.contains(id)).peekable();
+        if found.peek().is_some() {
+            list.push(
+                column![
+                    text!("{} ({})", group.runner, group.file.display()).style(text::primary),
+                    text!("in {}", group.dir.display())
+                        .size(12)
+                        .style(text::secondary),
+                ]
+                .into(),
+            );
+        }
+        for (id, found) in found {
+            let target = &group.targets[id - offset];
+            let name = highlighted(target.name(), &found.positions, highlight);
+            // favourites are marked, they are listed first
+            let name = match favourites.iter().any(|f| f == target.name()) {
+                true => row![text("★").style(text::primary), name].spacing(5).into(),
+                false => name,
+            };
+            list.push(target_card_for(
+                *id,
+                group,
+                target,
+                task_history,
+                name,
+                *id == selected,
+            ));
+        }
+        offset = ids.end;
+    }
+}
+
+fn target_card_for<'a>(
+    id: usize,
+    group: &'a Group,
+    target: &'a Target,
+    task_history: &'a [StdOutput],
+    name: Element<'a, Message>,
+    selected: bool,
+) -> Element<'a, Message> {
+    let status = match task_history.iter().rev().find(|t| t.id() == id) {
+        Some(task) => task.badge(),
+        None => row![].into(),
+    };
+    // Targets from included makefiles say which file they come from.
+    let name: Element<'a, Message> = match group.source(target) {
+        Some(source) => row![name, text(source).size(12).style(text::secondary)]
+            .spacing(5)
+            .align_y(iced::Alignment::Center)
+            .into(),
+        None => name,
+    };
+    let name = button(name)
+        .style(button::text)
+        .padding(0)
+        .on_press(Message::TargetSelected(id));
+    let label = match &target.description {
+        Some(description) => {
+            column![name, text(description).size(12).style(text::secondary)].into()
+        }
+        None => name.into(),
+    };
+    target_card(
+        action(
+            icons::start_icon(),
+            text(target.command.command_line()),
+            Some(Message::TaskMake(id, target.command.clone())),
+        ),
+        label,
+        row![
+            action(icons::stop_icon(), "stop", Some(Message::TaskStop(id))),
+            action(
+                text("…"),
+                "run with options",
+                Some(Message::RunDialogOpen(id, target.command.clone())),
+            ),
+        ]
+        .spacing(1)
+        .into(),
+        status,
+        selected,
+    )
+}
+
+/// What the target at `offset + index` in `group` depends on and what
+/// depends on it. Prerequisites that are files rather than targets can't
+/// be run or selected.
+pub fn dependency_graph<'a>(
+    group: &'a Group,
+    offset: usize,
+    target: &'a Target,
+) -> Element<'a, Message> {
+    let node = |(index, target): (usize, &'a Target)| -> Element<'a, Message> {
+        let id = offset + index;
+        row![
             action(
                 icons::start_icon(),
-                target,
-                Some(Message::TaskMake(id, target.clone())),
+                text(target.command.command_line()),
+                Some(Message::TaskMake(id, target.command.clone())),
             ),
-            target,
-            action(icons::stop_icon(), "stop", Some(Message::TaskStop(id))),
-        ));
-    }
+            button(text(target.name()).size(14))
+                .style(button::text)
+                .padding(0)
+                .on_press(Message::TargetSelected(id)),
+        ]
+        .spacing(5)
+        .align_y(iced::Alignment::Center)
+        .into()
+    };
+    let prerequisites =
+        target
+            .prerequisites
+            .iter()
+            .map(|prerequisite| match group.find(prerequisite) {
+                Some(found) => node(found),
+                None => text(prerequisite).size(14).style(text::secondary).into(),
+            });
+    let dependents = group.dependents(target.name()).map(node);
+    let heading = |label: &'a str| text(label).size(12).style(text::primary);
+    column![
+        text(target.name()),
+        heading("depends on"),
+        column(prerequisites).spacing(2),
+        heading("depended on by"),
+        column(dependents).spacing(2),
+    ]
+    .spacing(5)
+    .into()
 }
@@ -0,0 +1,191 @@
+use iced::widget::{button, checkbox, column, container, row, text, text_input, TextInput};
+use iced::{Element, Length::Fill};
+
+use cracker_core::utils::{quote, split_args, Error};
+
+use crate::{
+    stdout::worker::{RunOptions, StdCommand},
+    Message, RUN_DIALOG_ID,
+};
+
+/// An edit of one field of the run options dialog.
+#[derive(Debug, Clone)]
+pub enum Field {
+    Variables(String),
+    Jobs(String),
+    KeepGoing(bool),
+    Directory(String),
+    ExtraArgs(String),
+    Pty(bool),
+}
+
+/// Run options of a target being edited, fields are kept as typed and
+/// parsed when the target is run.
+#[derive(Debug, Clone)]
+pub struct RunDialog {
+    id: usize,
+    command: StdCommand,
+    variables: String,
+    jobs: String,
+    keep_going: bool,
+    directory: String,
+    extra_args: String,
+    pty: bool,
+}
+
+fn join(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl RunDialog {
+    pub fn new(id: usize, command: StdCommand, options: &RunOptions) -> Self {
+        Self {
+            id,
+            command,
+            variables: join(&options.variables),
+            jobs: options
+                .jobs
+                .map(|jobs| jobs.to_string())
+                .unwrap_or_default(),
+            keep_going: options.keep_going,
+            directory: options
+                .directory
+                .as_ref()
+                .map(|directory| directory.display().to_string())
+                .unwrap_or_default(),
+            extra_args: join(&options.extra_args),
+            pty: options.pty,
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The target's command without options.
+    pub fn command(&self) -> &StdCommand {
+        &self.command
+    }
+
+    pub fn update(&mut self, field: Field) {
+        match field {
+            Field::Variables(variables) => self.variables = variables,
+            Field::Jobs(jobs) => self.jobs = jobs,
+            Field::KeepGoing(keep_going) => self.keep_going = keep_going,
+            Field::Directory(directory) => self.directory = directory,
+            Field::ExtraArgs(extra_args) => self.extra_args = extra_args,
+            Field::Pty(pty) => self.pty = pty,
+        }
+    }
+
+    /// The options as typed, the variables, jobs, keep going and directory
+    /// only for commands whose runner takes them.
+    pub fn options(&self) -> Result<RunOptions, Error> {
+        let extra_args = split_args(&self.extra_args)?;
+        if !self.command.takes_options() {
+            return Ok(RunOptions {
+                extra_args,
+                pty: self.pty,
+                ..RunOptions::default()
+            });
+        }
+        let variables = split_args(&self.variables)?;
+        if let Some(variable) = variables.iter().find(|v| !v.contains('=')) {
+            return Err(Error::Parse(format!(
+                "not a VAR=value override: {variable}"
+            )));
+        }
+        let jobs = match self.jobs.trim() {
+            "" => None,
+            jobs => Some(
+                jobs.parse()
+                    .map_err(|_| Error::Parse(format!("invalid number of jobs: {jobs}")))?,
+            ),
+        };
+        let directory = match self.directory.trim() {
+            "" => None,
+            directory => Some(directory.into()),
+        };
+        Ok(RunOptions {
+            variables,
+            jobs,
+            keep_going: self.keep_going,
+            directory,
+            extra_args,
+            pty: self.pty,
+        })
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let input = |placeholder: &str, value: &str, on_input: fn(String) -> Field| {
+            text_input(placeholder, value)
+                .on_input(move |value| Message::RunDialogChanged(on_input(value)))
+                .on_submit(Message::RunDialogSubmit)
+                .size(14)
+        };
+        let field = |label: &'static str, input: TextInput<'static, Message>| {
+            row![text(label).size(14).width(120), input]
+                .spacing(10)
+                .align_y(iced::Alignment::Center)
+        };
+        // The exact command line, or why the options can't be used.
+        let preview = match self.options() {
+            Ok(options) => {
+                text(self.command.clone().with_options(options).command_line()).style(text::primary)
+            }
+            Err(Error::Parse(message)) => text(message).style(text::danger),
+            Err(error) => text!("{error:?}").style(text::danger),
+        };
+        let extra_args = input("args ...", &self.extra_args, Field::ExtraArgs);
+        let mut fields = column![text!("run {}", self.command.target())].spacing(10);
+        // make's own options, other tools only get the extra arguments
+        fields = match self.command.takes_options() {
+            true => fields
+                .push(field(
+                    "variables",
+                    input("VAR=value ...", &self.variables, Field::Variables)
+                        .id(RUN_DIALOG_ID.clone()),
+                ))
+                .push(field("jobs (-j)", input("N", &self.jobs, Field::Jobs)))
+                .push(
+                    checkbox("keep going (-k)", self.keep_going)
+                        .on_toggle(|keep_going| {
+                            Message::RunDialogChanged(Field::KeepGoing(keep_going))
+                        })
+                        .text_size(14),
+                )
+                .push(field(
+                    "directory (-C)",
+                    input("dir", &self.directory, Field::Directory),
+                ))
+                .push(field("extra args", extra_args)),
+            false => fields.push(field("extra args", extra_args.id(RUN_DIALOG_ID.clone()))),
+        };
+        container(
+            fields
+                .push(
+                    checkbox("run in a terminal (pty)", self.pty)
+                        .on_toggle(|pty| Message::RunDialogChanged(Field::Pty(pty)))
+                        .text_size(14),
+                )
+                .push(preview.size(14))
+                .push(
+                    row![
+                        button(text("cancel").size(14))
+                            .style(button::secondary)
+                            .on_press(Message::RunDialogClose),
+                        button(text("run").size(14)).on_press(Message::RunDialogSubmit),
+                    ]
+                    .spacing(10),
+                ),
+        )
+        .style(container::bordered_box)
+        .padding(20)
+        .width(Fill)
+        .max_width(600)
+        .into()
+    }
+}
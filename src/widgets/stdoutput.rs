@@ -1,82 +1,480 @@
-use iced::widget::{text, Column};
-use iced::{Element, Font, Subscription};
+use iced::font::{Style, Weight};
+use iced::futures::channel::mpsc;
+use iced::widget::text::{Span, Wrapping};
+use iced::widget::{
+    button, container, rich_text, row, scrollable, span, text, text_input, Column, Space,
+};
+use iced::{Color, Element, Font, Subscription};
+use std::fmt;
+use std::ops::Range;
 use tokio::time::Instant;
 
+use cracker_core::ansi;
+use cracker_core::history::Record;
+use cracker_core::search::Searcher;
+use cracker_core::utils::format_duration;
+
 use crate::{
-    stdout::worker::{self, StdCommand},
-    task_runners::makefile,
-    Message,
+    icons,
+    stdout::worker::{self, Control, Exit, Line, Source, StdCommand},
+    Message, INPUT_ID,
 };
 
+fn color(color: ansi::Color) -> Color {
+    let (r, g, b) = color.rgb();
+    Color::from_rgb8(r, g, b)
+}
+
+/// Height of an output line, 16px monospace text. Lines don't wrap so
+/// every line is one row.
+pub const LINE_HEIGHT: f32 = 20.8;
+
+/// Rows laid out above and below the viewport.
+const OVERSCAN: usize = 20;
+
+/// Background of search matches, and of the one navigated to.
+const MATCH: Color = Color::from_rgba(1.0, 0.85, 0.0, 0.35);
+const CURRENT_MATCH: Color = Color::from_rgba(1.0, 0.55, 0.0, 0.8);
+
+/// `text` in its ANSI style, on a search match colour when it is in one.
+fn ansi_span<'a>(text: &str, style: ansi::Style, found: Option<Color>) -> Span<'a, Message> {
+    let (fg, bg) = match style.inverse {
+        true => (
+            Some(style.bg.unwrap_or(ansi::Color::Indexed(0))),
+            Some(style.fg.unwrap_or(ansi::Color::Indexed(7))),
+        ),
+        false => (style.fg, style.bg),
+    };
+    let fg = fg.map(color).map(|fg| match style.dim {
+        true => fg.scale_alpha(0.6),
+        false => fg,
+    });
+    let font = Font {
+        weight: if style.bold {
+            Weight::Bold
+        } else {
+            Weight::Normal
+        },
+        style: if style.italic {
+            Style::Italic
+        } else {
+            Style::Normal
+        },
+        ..Font::MONOSPACE
+    };
+    span(text.to_string())
+        .font(font)
+        .color_maybe(fg)
+        .background_maybe(found.or(bg.map(color)))
+        .underline(style.underline)
+}
+
+/// A line of output with its ANSI styles and search matches, given as byte
+/// ranges of its shown text and whether it is the current one. Stderr is in
+/// the danger colour unless the program set one.
+fn styled<'a>(line: &'a Line, found: &[(Range<usize>, bool)]) -> Element<'a, Message> {
+    let mut spans: Vec<Span<'a, Message>> = Vec::new();
+    let mut offset = 0;
+    for ansi::Span { text, style } in ansi::parse(&line.text) {
+        let end = offset + text.len();
+        // the span is cut where matches start and end
+        let mut cuts: Vec<usize> = found
+            .iter()
+            .flat_map(|(range, _)| [range.start, range.end])
+            .filter(|cut| (offset + 1..end).contains(cut))
+            .chain([end])
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+        let mut start = offset;
+        for cut in cuts {
+            let background = found
+                .iter()
+                .find(|(range, _)| range.contains(&start))
+                .map(|(_, current)| if *current { CURRENT_MATCH } else { MATCH });
+            spans.push(ansi_span(
+                &text[start - offset..cut - offset],
+                style,
+                background,
+            ));
+            start = cut;
+        }
+        offset = end;
+    }
+    let line_text = rich_text(spans)
+        .font(Font::MONOSPACE)
+        .wrapping(Wrapping::None);
+    let line_text = match line.source {
+        Source::Stdout => line_text,
+        Source::Stderr => line_text.style(text::danger),
+    };
+    container(line_text).height(LINE_HEIGHT).into()
+}
+
 // StdOutput
 #[derive(Debug)]
 pub struct StdOutput {
     id: usize,
+    run: usize,
     command: StdCommand,
+    control: Option<mpsc::Sender<Control>>,
     state: State,
-    textbox_output: Vec<String>,
+    started: Option<Instant>,
+    exit: Option<Exit>,
+    textbox_output: Vec<Line>,
+    /// Indices of the stdout and of the stderr lines, the rows of the output
+    /// filtered by stream.
+    stdout_lines: Vec<usize>,
+    stderr_lines: Vec<usize>,
+    /// Unfinished last line, like a prompt waiting for input.
+    partial: Option<Line>,
+    /// Line being typed for the task's stdin.
+    input: String,
+    /// Output lines kept, older ones are dropped.
+    max_lines: usize,
+    /// Keep the output view at the end as lines come in.
+    auto_scroll: bool,
+    /// Where the output view was left, restored when its tab is shown again.
+    offset: scrollable::RelativeOffset,
+    /// Scroll position in the output lines, where the viewport starts.
+    top: f32,
+    /// Output search, the lines it looks in and its matches as line index
+    /// and byte range of the shown text.
+    searcher: Option<Searcher>,
+    search_filter: StreamFilter,
+    matches: Vec<(usize, Range<usize>)>,
+    /// Index in `matches` of the match navigated to.
+    current_match: Option<usize>,
     tick: Instant,
     ms_200: core::time::Duration,
 }
 
+/// Which of a task's output streams are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StreamFilter {
+    #[default]
+    All,
+    Stdout,
+    Stderr,
+}
+
+impl StreamFilter {
+    pub const ALL: [StreamFilter; 3] = [
+        StreamFilter::All,
+        StreamFilter::Stdout,
+        StreamFilter::Stderr,
+    ];
+
+    fn shows(&self, source: Source) -> bool {
+        match self {
+            StreamFilter::All => true,
+            StreamFilter::Stdout => source == Source::Stdout,
+            StreamFilter::Stderr => source == Source::Stderr,
+        }
+    }
+}
+
+impl fmt::Display for StreamFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamFilter::All => write!(f, "stdout + stderr"),
+            StreamFilter::Stdout => write!(f, "stdout"),
+            StreamFilter::Stderr => write!(f, "stderr"),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum State {
     Idle,
-    Streaming { stream: Vec<String> },
+    Streaming,
+    Stopping,
     Finished,
     Errored,
 }
 
+/// Pass/fail status of a finished run and how long it took.
+pub fn exit_badge<'a>(exit: &Exit) -> Element<'a, Message> {
+    let duration = format_duration(exit.duration);
+    let badge = if exit.stopped {
+        text!("STOPPED {duration}").style(text::secondary)
+    } else if exit.success() {
+        text!("PASS {duration}").style(text::success)
+    } else if let Some(code) = exit.code {
+        text!("FAIL exit {code} {duration}").style(text::danger)
+    } else {
+        let signal = exit.signal_name().unwrap_or("signal");
+        text!("FAIL {signal} {duration}").style(text::danger)
+    };
+    container(badge.size(12))
+        .style(container::bordered_box)
+        .padding([0, 4])
+        .into()
+}
+
 impl StdOutput {
-    pub fn new(id: usize, target: String) -> Self {
+    pub fn new(id: usize, run: usize, command: StdCommand) -> Self {
         let tick = Instant::now();
         Self {
             id,
-            command: makefile::new(target),
+            run,
+            command,
+            control: None,
             state: State::Idle,
+            started: None,
+            exit: None,
             textbox_output: Vec::new(),
+            stdout_lines: Vec::new(),
+            stderr_lines: Vec::new(),
+            partial: None,
+            input: String::new(),
+            max_lines: 1_000_000,
+            auto_scroll: true,
+            offset: scrollable::RelativeOffset::END,
+            top: 0.0,
+            searcher: None,
+            search_filter: StreamFilter::All,
+            matches: Vec::new(),
+            current_match: None,
             tick,
             ms_200: core::time::Duration::from_millis(200),
         }
     }
-    pub fn target(&self) -> String {
+    /// A finished run read back from the history.
+    pub fn restored(id: usize, run: usize, record: Record, output: Vec<Line>) -> Self {
+        let mut task = Self::new(id, run, record.command);
+        task.state = State::Finished;
+        task.exit = Some(record.exit);
+        task.push(output);
+        task.offset = scrollable::RelativeOffset::START;
+        task
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    pub fn command(&self) -> &StdCommand {
+        &self.command
+    }
+
+    pub fn exit(&self) -> Option<&Exit> {
+        self.exit.as_ref()
+    }
+
+    pub fn output(&self) -> &[Line] {
+        &self.textbox_output
+    }
+
+    pub fn target(&self) -> &str {
         self.command.target()
     }
 
     pub fn id(&self) -> usize {
         self.id
     }
+
+    pub fn run(&self) -> usize {
+        self.run
+    }
+
+    pub fn running(&self) -> bool {
+        matches!(self.state, State::Streaming | State::Stopping)
+    }
+
+    pub fn auto_scroll(&self) -> bool {
+        self.auto_scroll
+    }
+
+    pub fn toggle_auto_scroll(&mut self) {
+        self.auto_scroll = !self.auto_scroll;
+    }
+
+    pub fn offset(&self) -> scrollable::RelativeOffset {
+        self.offset
+    }
+
+    /// Scrolls to `offset`, which is `top` in the output lines.
+    pub fn scrolled(&mut self, offset: scrollable::RelativeOffset, top: f32) {
+        self.offset = offset;
+        self.top = top;
+    }
+
+    /// Adds output lines, indexing them by stream and searching them.
+    fn push(&mut self, lines: impl IntoIterator<Item = Line>) {
+        let first = self.textbox_output.len();
+        self.textbox_output.extend(lines);
+        for (i, line) in self.textbox_output.iter().enumerate().skip(first) {
+            match line.source {
+                Source::Stdout => self.stdout_lines.push(i),
+                Source::Stderr => self.stderr_lines.push(i),
+            }
+        }
+        self.find_matches(first);
+    }
+
+    /// Drops the `count` oldest output lines with their matches.
+    fn drop_lines(&mut self, count: usize) {
+        self.textbox_output.drain(..count);
+        for lines in [&mut self.stdout_lines, &mut self.stderr_lines] {
+            let dropped = lines.partition_point(|line| *line < count);
+            lines.drain(..dropped);
+            lines.iter_mut().for_each(|line| *line -= count);
+        }
+        let dropped = self.matches.partition_point(|(line, _)| *line < count);
+        self.matches.drain(..dropped);
+        self.matches.iter_mut().for_each(|(line, _)| *line -= count);
+        self.current_match = self
+            .current_match
+            .and_then(|current| current.checked_sub(dropped));
+    }
+
+    /// Searches the output lines `filter` shows with `searcher`, and the
+    /// lines still to come.
+    pub fn search(&mut self, searcher: Option<Searcher>, filter: StreamFilter) {
+        self.searcher = searcher;
+        self.search_filter = filter;
+        self.matches.clear();
+        self.current_match = None;
+        self.find_matches(0);
+    }
+
+    /// Adds the matches in the lines from `first` on.
+    fn find_matches(&mut self, first: usize) {
+        let Some(searcher) = &self.searcher else {
+            return;
+        };
+        for (i, line) in self.textbox_output.iter().enumerate().skip(first) {
+            if self.search_filter.shows(line.source) {
+                let text = ansi::strip(&line.text);
+                self.matches
+                    .extend(searcher.find(&text).map(|range| (i, range)));
+            }
+        }
+    }
+
+    /// Matches in the whole output, and which one is navigated to.
+    pub fn matches(&self) -> (usize, Option<usize>) {
+        (self.matches.len(), self.current_match)
+    }
+
+    /// Moves to the next match, or the previous one, wrapping around, and
+    /// gives where its row is in the output lines. The output stops following
+    /// new lines so the match stays in view.
+    pub fn next_match(&mut self, forward: bool) -> Option<f32> {
+        let count = self.matches.len();
+        if count == 0 {
+            return None;
+        }
+        let current = match (self.current_match, forward) {
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+            (None, true) => 0,
+            (None, false) => count - 1,
+        };
+        self.current_match = Some(current);
+        self.auto_scroll = false;
+        let row = self.row_of(self.search_filter, self.matches[current].0)?;
+        Some(row as f32 * LINE_HEIGHT)
+    }
+
     pub fn start(&mut self) {
         info!("start task {:?}", self.target());
         match self.state {
-            State::Idle { .. } | State::Finished { .. } | State::Errored { .. } => {
-                self.state = State::Streaming {
-                    stream: vec!["Stream started...".to_string()],
-                };
+            State::Idle | State::Finished | State::Errored => {
+                self.state = State::Streaming;
+                self.started = Some(Instant::now());
+                self.exit = None;
             }
-            State::Streaming { .. } => {}
+            State::Streaming | State::Stopping => {}
+        }
+    }
+
+    pub fn set_input(&mut self, input: String) {
+        self.input = input;
+    }
+
+    /// Sends the typed line to the task's stdin.
+    pub fn send_input(&mut self) {
+        let line = format!("{}\n", self.input);
+        if self.send(Control::Input(line)) {
+            self.input.clear();
+        }
+    }
+
+    /// Ends the task's stdin, like ctrl-d in a terminal.
+    pub fn send_eof(&mut self) {
+        self.send(Control::Eof);
+    }
+
+    /// Sends `control` to the running worker, if there is one.
+    fn send(&mut self, control: Control) -> bool {
+        let sent = self
+            .control
+            .as_mut()
+            .is_some_and(|sender| sender.try_send(control).is_ok());
+        if !sent {
+            warn!("task {:?} is not taking input", self.target());
+        }
+        sent
+    }
+
+    /// Resizes the terminal of a running task started in one.
+    pub fn resize(&mut self, (cols, rows): (u16, u16)) {
+        if let Some(control) = self.control.as_mut().filter(|_| self.command.options().pty) {
+            let _ = control.try_send(Control::Resize { cols, rows });
         }
     }
 
     pub fn stop(&mut self) {
+        let State::Streaming = self.state else {
+            return;
+        };
         info!("stopping task {:?}", self.target());
+        // Ask the worker to signal the task and wait for it to report back.
+        // Without a control channel the worker has not spawned anything yet,
+        // so dropping its subscription is enough.
+        let sent = self
+            .control
+            .as_mut()
+            .is_some_and(|control| control.try_send(Control::Stop).is_ok());
+        if sent {
+            self.state = State::Stopping;
+            self.push([Line::stdout(""), Line::stdout("stopping...")]);
+        } else {
+            self.end_stream();
+        }
+    }
+
+    fn end_stream(&mut self) {
         self.state = State::Finished;
-        let end_stream = vec!["".to_string(), "stream ended...".to_string()];
-        self.textbox_output.extend(end_stream);
+        self.control = None;
+        self.push([Line::stdout(""), Line::stdout("stream ended...")]);
     }
+
     pub fn stream_update(&mut self, output_update: Result<worker::Stdout, worker::Error>) {
-        if let State::Streaming { .. } = &mut self.state {
+        if let State::Streaming | State::Stopping = &mut self.state {
             match output_update {
-                Ok(worker::Stdout::OutputUpdate { output }) => {
-                    self.textbox_output.extend(output);
-                    // *stream = output
+                Ok(worker::Stdout::Ready(control)) => {
+                    self.control = Some(control);
                 }
-                Ok(worker::Stdout::Finished) => {
-                    self.state = State::Finished;
+                Ok(worker::Stdout::OutputUpdate { output }) => {
+                    self.partial = None;
+                    self.push(output);
                 }
-                Ok(worker::Stdout::Prepare { output }) => {
-                    self.textbox_output.extend(output);
+                Ok(worker::Stdout::Finished(exit)) => {
+                    info!("task {:?} finished: {exit:?}", self.target());
+                    if exit.stopped {
+                        self.end_stream();
+                    } else {
+                        self.state = State::Finished;
+                        self.control = None;
+                    }
+                    self.exit = Some(exit);
                 }
+                Ok(worker::Stdout::Partial(line)) => self.partial = Some(line),
+                Ok(worker::Stdout::Prepare { output }) => self.push(output),
 
                 Err(worker::Error::NoContent) => {
                     self.state = State::Errored;
@@ -86,41 +484,260 @@ impl StdOutput {
                 }
             }
         }
-        if self.tick.elapsed() >= self.ms_200 && self.textbox_output.len() > 1_000_000 {
-            let r = self.textbox_output.len() - 1_000_000;
-            self.textbox_output.drain(..r);
+        if self.tick.elapsed() >= self.ms_200 && self.textbox_output.len() > self.max_lines {
+            self.drop_lines(self.textbox_output.len() - self.max_lines);
             self.tick = Instant::now();
         }
     }
     pub fn subscription(&self) -> Subscription<Message> {
         match self.state {
-            State::Streaming { .. } => {
-                worker::subscription(self.id, self.command.clone()).map(Message::TaskUpdate)
+            State::Streaming | State::Stopping => {
+                worker::subscription(self.run, self.command.clone()).map(Message::TaskUpdate)
             }
             _ => Subscription::none(),
         }
     }
 
-    pub fn view(&self) -> Element<Message> {
-        fn get_window(len: usize, width: usize) -> usize {
-            if len > width {
-                return len - width;
+    /// Pass/fail status of the run and how long it took, or has been running.
+    pub fn badge(&self) -> Element<'_, Message> {
+        let badge = match (&self.state, &self.exit) {
+            (State::Streaming | State::Stopping, _) => {
+                let elapsed = self.started.map(|s| s.elapsed()).unwrap_or_default();
+                text!("RUNNING {}", format_duration(elapsed)).style(text::primary)
             }
-            0
-        }
-        let idx = get_window(
-            self.textbox_output.len(),
-            match self.state {
-                State::Finished => 1_000,
-                _ => 100,
-            },
-        );
-        Column::with_children(
-            self.textbox_output[idx..]
-                .iter()
-                .map(|o| text!("{}", o).font(Font::MONOSPACE))
-                .map(Element::from),
+            (State::Errored, _) => text("ERROR").style(text::danger),
+            (_, Some(exit)) => return exit_badge(exit),
+            _ => return row![].into(),
+        };
+        container(badge.size(12))
+            .style(container::bordered_box)
+            .padding([0, 4])
+            .into()
+    }
+
+    /// Tab of the run in the output view, with its status and buttons to
+    /// stop and close it.
+    pub fn tab(&self, active: bool) -> Element<'_, Message> {
+        let stop = if self.running() {
+            Some(Message::TaskStopRun(self.run))
+        } else {
+            None
+        };
+        let label = button(text(self.target()).size(14))
+            .style(if active {
+                button::primary
+            } else {
+                button::text
+            })
+            .padding([2, 6])
+            .on_press(Message::TabSelected(self.run));
+        container(
+            row![
+                label,
+                self.badge(),
+                super::action(icons::stop_icon(), "stop", stop),
+                super::action(text("×"), "close", Some(Message::TabClosed(self.run))),
+            ]
+            .spacing(4)
+            .align_y(iced::Alignment::Center),
         )
+        .style(container::bordered_box)
+        .padding(2)
         .into()
     }
+
+    /// Output lines `filter` shows.
+    fn rows(&self, filter: StreamFilter) -> usize {
+        match filter {
+            StreamFilter::All => self.textbox_output.len(),
+            StreamFilter::Stdout => self.stdout_lines.len(),
+            StreamFilter::Stderr => self.stderr_lines.len(),
+        }
+    }
+
+    /// Index of the line at `row` of the output `filter` shows.
+    fn line_at(&self, filter: StreamFilter, row: usize) -> usize {
+        match filter {
+            StreamFilter::All => row,
+            StreamFilter::Stdout => self.stdout_lines[row],
+            StreamFilter::Stderr => self.stderr_lines[row],
+        }
+    }
+
+    /// Row of the line at `line` in the output `filter` shows.
+    fn row_of(&self, filter: StreamFilter, line: usize) -> Option<usize> {
+        match filter {
+            StreamFilter::All => Some(line),
+            StreamFilter::Stdout => self.stdout_lines.binary_search(&line).ok(),
+            StreamFilter::Stderr => self.stderr_lines.binary_search(&line).ok(),
+        }
+    }
+
+    /// Rows in a viewport of `height` at the scroll position, and the last
+    /// ones while following a running task, with some rows around them so
+    /// scrolling doesn't show blank space before the view catches up.
+    fn visible_rows(&self, filter: StreamFilter, height: f32) -> Vec<Range<usize>> {
+        let rows = self.rows(filter);
+        let visible = (height / LINE_HEIGHT).ceil() as usize + 1;
+        let last_page = rows.saturating_sub(visible);
+        let first = ((self.top / LINE_HEIGHT) as usize).min(last_page);
+        let mut around = first.saturating_sub(OVERSCAN)..(first + visible + OVERSCAN).min(rows);
+        let mut tail = None;
+        if self.auto_scroll && self.running() {
+            let start = last_page.saturating_sub(OVERSCAN);
+            match start <= around.end {
+                true => around.end = rows,
+                false => tail = Some(start..rows),
+            }
+        }
+        [Some(around), tail]
+            .into_iter()
+            .flatten()
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+
+    /// Header of the output, the command line, its status and directory.
+    pub fn header(&self) -> Element<'_, Message> {
+        let mut header = row![text(self.command.command_line()), self.badge()].spacing(10);
+        if let Some(dir) = self.command.dir() {
+            header = header.push(
+                text!("in {}", dir.display())
+                    .size(12)
+                    .style(text::secondary),
+            );
+        }
+        header.into()
+    }
+
+    /// Input box for the task's stdin, while it is running.
+    pub fn input(&self) -> Option<Element<'_, Message>> {
+        let run = self.run;
+        self.running().then(|| {
+            row![
+                text_input("stdin", &self.input)
+                    .id(INPUT_ID.clone())
+                    .on_input(move |input| Message::TaskInputChanged(run, input))
+                    .on_submit(Message::TaskInputSubmit(run))
+                    .font(Font::MONOSPACE)
+                    .size(14),
+                button(text("EOF").size(14))
+                    .style(button::secondary)
+                    .on_press(Message::TaskInputEof(run)),
+            ]
+            .spacing(5)
+            .into()
+        })
+    }
+
+    /// The output `filter` shows in a viewport of `height`. Every line is
+    /// one row high so only the rows in view are laid out, with space for
+    /// the others.
+    pub fn view(&self, filter: StreamFilter, height: f32) -> Element<'_, Message> {
+        let space = |rows: usize| Space::with_height(rows as f32 * LINE_HEIGHT).into();
+        let mut lines: Vec<Element<'_, Message>> = Vec::new();
+        let mut next = 0;
+        for range in self.visible_rows(filter, height) {
+            if range.start > next {
+                lines.push(space(range.start - next));
+            }
+            let first_line = self.line_at(filter, range.start);
+            let first = self.matches.partition_point(|(line, _)| *line < first_line);
+            let mut matches = self.matches.iter().enumerate().skip(first).peekable();
+            for row in range.clone() {
+                let i = self.line_at(filter, row);
+                let mut found = Vec::new();
+                while let Some((n, (line, range))) = matches.next_if(|(_, (line, _))| *line <= i) {
+                    if *line == i {
+                        found.push((range.clone(), self.current_match == Some(n)));
+                    }
+                }
+                lines.push(styled(&self.textbox_output[i], &found));
+            }
+            next = range.end;
+        }
+        let rows = self.rows(filter);
+        if rows > next {
+            lines.push(space(rows - next));
+        }
+        lines.extend(self.partial.iter().map(|line| styled(line, &[])));
+        Column::with_children(lines).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Viewport ten and a half rows high, eleven rows with the one cut off.
+    const HEIGHT: f32 = 10.5 * LINE_HEIGHT;
+
+    fn task(lines: impl IntoIterator<Item = Line>) -> StdOutput {
+        let mut task = StdOutput::new(0, 0, StdCommand::new("all".into(), "make".into()));
+        task.push(lines);
+        task
+    }
+
+    #[test]
+    fn test_empty_output() {
+        let mut task = task([]);
+        task.start();
+        for filter in StreamFilter::ALL {
+            assert_eq!(task.rows(filter), 0);
+            assert!(task.visible_rows(filter, HEIGHT).is_empty());
+            task.view(filter, HEIGHT);
+        }
+        // a clean build has nothing on stderr
+        let task = self::task([Line::stdout("ok")]);
+        assert!(task.visible_rows(StreamFilter::Stderr, HEIGHT).is_empty());
+        task.view(StreamFilter::Stderr, HEIGHT);
+    }
+
+    #[test]
+    fn test_rows_by_stream() {
+        let task = task((0..10).map(|i| match i % 3 {
+            0 => Line::stderr(format!("{i}")),
+            _ => Line::stdout(format!("{i}")),
+        }));
+        assert_eq!(task.rows(StreamFilter::All), 10);
+        assert_eq!(task.rows(StreamFilter::Stdout), 6);
+        assert_eq!(task.rows(StreamFilter::Stderr), 4);
+        assert_eq!(task.line_at(StreamFilter::Stderr, 2), 6);
+        assert_eq!(task.line_at(StreamFilter::Stdout, 2), 4);
+        assert_eq!(task.row_of(StreamFilter::Stdout, 5), Some(3));
+        assert_eq!(task.row_of(StreamFilter::Stdout, 6), None);
+    }
+
+    #[test]
+    fn test_visible_rows() {
+        let mut task = task((0..1_000).map(|i| Line::stdout(format!("{i}"))));
+        assert_eq!(task.visible_rows(StreamFilter::All, HEIGHT), vec![0..32]);
+        task.top = 500.0 * LINE_HEIGHT;
+        assert_eq!(task.visible_rows(StreamFilter::All, HEIGHT), vec![480..532]);
+        // scrolled past the end, the last page is shown
+        task.top = f32::INFINITY;
+        assert_eq!(
+            task.visible_rows(StreamFilter::All, HEIGHT),
+            vec![968..1_000]
+        );
+    }
+
+    #[test]
+    fn test_visible_rows_follow_tail() {
+        let mut task = task((0..1_000).map(|i| Line::stdout(format!("{i}"))));
+        task.start();
+        assert_eq!(
+            task.visible_rows(StreamFilter::All, HEIGHT),
+            vec![0..32, 968..1_000]
+        );
+        task.top = 960.0 * LINE_HEIGHT;
+        assert_eq!(
+            task.visible_rows(StreamFilter::All, HEIGHT),
+            vec![940..1_000]
+        );
+        // scrolled up without following, only the viewport
+        task.toggle_auto_scroll();
+        task.top = 0.0;
+        assert_eq!(task.visible_rows(StreamFilter::All, HEIGHT), vec![0..32]);
+    }
 }